@@ -20,12 +20,49 @@ function Write-Result {
     [System.IO.File]::WriteAllText($ResultPath, $json, $utf8NoBom)
 }
 
+function Write-Log {
+    param([string]$Message)
+
+    if (-not $log -or [string]::IsNullOrWhiteSpace($Message)) {
+        return
+    }
+    try {
+        Add-Content -Path $log -Value $Message.TrimEnd() -Encoding UTF8
+    } catch {
+    }
+}
+
+# Progress lines are appended to the --progress file as one JSON object per line:
+# {"stage":"<stage>","message":"<optional detail>"}
+function Write-Step {
+    param(
+        [string]$Stage,
+        [string]$Message
+    )
+
+    if (-not $progress) {
+        return
+    }
+    $line = [ordered]@{
+        stage = $Stage
+        message = $Message
+    } | ConvertTo-Json -Compress
+    try {
+        Add-Content -Path $progress -Value $line -Encoding UTF8
+    } catch {
+    }
+}
+
 $base = $null
 $patch = $null
 $delta = '1'
+$readOnly = '0'
+$drive = 'X'
 $result = $null
 $signal = $null
 $done = $null
+$log = $null
+$progress = $null
 
 for ($i = 0; $i -lt $args.Length; $i++) {
     $key = $args[$i]
@@ -33,9 +70,13 @@ for ($i = 0; $i -lt $args.Length; $i++) {
         '--base' { $base = $args[$i + 1]; $i++ }
         '--patch' { $patch = $args[$i + 1]; $i++ }
         '--delta' { $delta = $args[$i + 1]; $i++ }
+        '--readonly' { $readOnly = $args[$i + 1]; $i++ }
+        '--drive' { $drive = $args[$i + 1]; $i++ }
         '--result' { $result = $args[$i + 1]; $i++ }
         '--signal' { $signal = $args[$i + 1]; $i++ }
         '--done' { $done = $args[$i + 1]; $i++ }
+        '--log' { $log = $args[$i + 1]; $i++ }
+        '--progress' { $progress = $args[$i + 1]; $i++ }
     }
 }
 
@@ -44,13 +85,27 @@ if (-not $patch -or -not $result -or -not $signal -or -not $done) {
     exit 1
 }
 
-if (Test-Path 'X:\') {
-    Write-Result $false $null $null 'Drive X: is already in use. Please eject or change the assigned drive.' $result
+if ($drive -notmatch '^[A-Za-z]$') {
+    Write-Result $false $null $null "Invalid drive letter: $drive" $result
+    exit 1
+}
+$drive = $drive.ToUpper()
+$driveRoot = "${drive}:\"
+
+if (Test-Path $driveRoot) {
+    Write-Result $false $null $null "Drive ${drive}: is already in use. Please eject or change the assigned drive." $result
     exit 1
 }
 
 $mountPath = $patch
 $runtimePath = $null
+$access = 'ReadWrite'
+if ($readOnly -eq '1' -or $readOnly -eq 'true' -or $readOnly -eq 'True') {
+    $access = 'ReadOnly'
+    $delta = '0'
+}
+
+Write-Step 'started' $mountPath
 
 try {
     if ($delta -eq '1' -or $delta -eq 'true' -or $delta -eq 'True') {
@@ -62,15 +117,18 @@ try {
         }
         $runtimePath = Join-Path $parentDir "$stem-runtime$ext"
 
+        Write-Step 'cleanup' $runtimePath
         Dismount-DiskImage -ImagePath $runtimePath -Confirm:$false -ErrorAction SilentlyContinue | Out-Null
         if (Test-Path $runtimePath) {
             Remove-Item $runtimePath -Force -ErrorAction SilentlyContinue
         }
 
+        Write-Step 'create_delta' $runtimePath
         $dpPath = Join-Path $env:TEMP ("configarc_vhd_diskpart_{0}.txt" -f $PID)
         $dpScript = "create vdisk file=`"$runtimePath`" parent=`"$patch`"`n"
         Set-Content -Path $dpPath -Value $dpScript -Encoding ASCII
-        & diskpart.exe /s $dpPath | Out-Null
+        $dpOutput = & diskpart.exe /s $dpPath 2>&1 | Out-String
+        Write-Log $dpOutput
         Remove-Item $dpPath -Force -ErrorAction SilentlyContinue
 
         if (-not (Test-Path $runtimePath)) {
@@ -80,24 +138,28 @@ try {
         $mountPath = $runtimePath
     }
 
-    Mount-DiskImage -ImagePath $mountPath -StorageType VHD -NoDriveLetter -Passthru -Access ReadWrite -Confirm:$false -ErrorAction Stop |
+    Write-Step 'mount' $driveRoot
+    Mount-DiskImage -ImagePath $mountPath -StorageType VHD -NoDriveLetter -Passthru -Access $access -Confirm:$false -ErrorAction Stop |
         Get-Disk |
         Get-Partition |
         Where-Object { ($_ | Get-Volume) -ne $Null } |
-        Add-PartitionAccessPath -AccessPath 'X:\' -ErrorAction Stop |
+        Add-PartitionAccessPath -AccessPath $driveRoot -ErrorAction Stop |
         Out-Null
 
     try {
         Start-Sleep -Milliseconds 300
         $shell = New-Object -ComObject Shell.Application
         $shell.Windows() | Where-Object {
-            $_.LocationURL -like 'file:///X:*' -or $_.LocationURL -like 'file:///X:/*'
+            $_.LocationURL -like "file:///${drive}:*" -or $_.LocationURL -like "file:///${drive}:/*"
         } | ForEach-Object { $_.Quit() }
     } catch {
     }
 
+    Write-Step 'ready' $driveRoot
     Write-Result $true $mountPath $runtimePath $null $result
 } catch {
+    Write-Step 'failed' $_.Exception.Message
+    Write-Log ($_ | Out-String)
     Write-Result $false $null $null $_.Exception.Message $result
     exit 1
 }
@@ -0,0 +1,119 @@
+use super::json_configs::is_allowed_json;
+use super::paths::{game_dir, segatools_root_for_game_id};
+use crate::error::ConfigError;
+use crate::trusted::clean_entry_path;
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+// Entries under `segatools/` are relative to the game's segatools root; entries under
+// `game/` are the `config_*.json` files next to the game executable.
+const SEGATOOLS_PREFIX: &str = "segatools/";
+const GAME_PREFIX: &str = "game/";
+pub(crate) const SEGATOOLS_FILES: &[&str] = &[
+  "segatools.ini",
+  "vhd.json",
+  "Segatools_Config/configarc_profiles.json",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleImportResult {
+  pub imported: Vec<String>,
+  pub skipped: Vec<String>,
+}
+
+fn zip_error(err: zip::result::ZipError) -> ConfigError {
+  ConfigError::Parse(err.to_string())
+}
+
+/// Maps a bundle entry onto its destination. Anything that isn't one of the known
+/// files is rejected, so an entry can never land outside the two game roots.
+fn bundle_target(entry: &str, segatools_root: &Path, game_root: &Path) -> Option<PathBuf> {
+  let clean = clean_entry_path(entry)?;
+  if let Some(rel) = clean.strip_prefix(SEGATOOLS_PREFIX) {
+    let known = SEGATOOLS_FILES.iter().find(|f| f.eq_ignore_ascii_case(rel))?;
+    return Some(segatools_root.join(known));
+  }
+  if let Some(name) = clean.strip_prefix(GAME_PREFIX) {
+    if !name.contains('/') && is_allowed_json(name) {
+      return Some(game_root.join(name));
+    }
+  }
+  None
+}
+
+/// Writes the game's segatools.ini, vhd.json, saved profiles and `config_*.json` files
+/// into a zip at `dest`. Returns the entry names that were written.
+pub fn export_bundle(game_id: &str, dest: &Path) -> Result<Vec<String>, ConfigError> {
+  let segatools_root = segatools_root_for_game_id(game_id);
+  let game_root = game_dir(game_id)?;
+
+  let mut entries: Vec<(String, PathBuf)> = SEGATOOLS_FILES
+    .iter()
+    .map(|f| (format!("{}{}", SEGATOOLS_PREFIX, f), segatools_root.join(f)))
+    .filter(|(_, path)| path.is_file())
+    .collect();
+  let mut json_files = Vec::new();
+  for entry in fs::read_dir(&game_root)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().to_string();
+    if entry.file_type()?.is_file() && is_allowed_json(&name) {
+      json_files.push((format!("{}{}", GAME_PREFIX, name), entry.path()));
+    }
+  }
+  json_files.sort();
+  entries.extend(json_files);
+
+  if entries.is_empty() {
+    return Err(ConfigError::NotFound("No configuration files to export".to_string()));
+  }
+
+  if let Some(parent) = dest.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let mut zip = ZipWriter::new(fs::File::create(dest)?);
+  let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+  for (name, path) in &entries {
+    zip.start_file(name.as_str(), options).map_err(zip_error)?;
+    zip.write_all(&fs::read(path)?)?;
+  }
+  zip.finish().map_err(zip_error)?;
+
+  Ok(entries.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Extracts a bundle produced by [`export_bundle`] into the game's directories.
+pub fn import_bundle(game_id: &str, path: &Path) -> Result<BundleImportResult, ConfigError> {
+  let segatools_root = segatools_root_for_game_id(game_id);
+  let game_root = game_dir(game_id)?;
+  let mut zip = ZipArchive::new(fs::File::open(path)?).map_err(zip_error)?;
+
+  let mut imported = Vec::new();
+  let mut skipped = Vec::new();
+  for i in 0..zip.len() {
+    let mut entry = zip.by_index(i).map_err(zip_error)?;
+    if !entry.is_file() {
+      continue;
+    }
+    let name = entry.name().to_string();
+    let Some(target) = bundle_target(&name, &segatools_root, &game_root) else {
+      skipped.push(name);
+      continue;
+    };
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    let mut out = fs::File::create(&target)?;
+    std::io::copy(&mut entry, &mut out)?;
+    drop(out);
+    if target == segatools_root.join("segatools.ini") {
+      super::record_segatoools_hash(&target);
+    }
+    imported.push(name);
+  }
+
+  Ok(BundleImportResult { imported, skipped })
+}
@@ -12,7 +12,91 @@ pub struct JsonConfigFile {
     pub kind: String,
 }
 
-fn is_allowed_json(name: &str) -> bool {
+/// A problem with one field of a config json; `field` is a dotted path, empty for the root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonFieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl JsonFieldError {
+    fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// What `validate_json_config` checks for one kind of config.
+struct KindSchema {
+    kind: &'static str,
+    /// Keys, at any depth, that hold a port number.
+    port_keys: &'static [&'static str],
+    /// Whether one of `SERVER_ADDRESS_KEYS` must be set.
+    needs_address: bool,
+}
+
+const KIND_SCHEMAS: &[KindSchema] = &[KindSchema {
+    kind: "server",
+    port_keys: &["port"],
+    needs_address: true,
+}];
+
+const SERVER_ADDRESS_KEYS: &[&str] = &["host", "server", "url", "address"];
+
+/// Calls `visit` with the dotted path, key and value of every object member under `value`.
+fn walk_fields(value: &Value, prefix: &str, visit: &mut dyn FnMut(&str, &str, &Value)) {
+    if let Value::Object(map) = value {
+        for (key, child) in map {
+            let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+            visit(&path, key, child);
+            walk_fields(child, &path, visit);
+        }
+    }
+}
+
+/// A port as a number or a numeric string, e.g. `80` or `"80"`.
+fn is_port(value: &Value) -> bool {
+    let port = match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => text.trim().parse::<u64>().ok(),
+        _ => None,
+    };
+    port.is_some_and(|port| port <= 65535)
+}
+
+/// Checks the fields a config of its kind needs to boot: every config is an object, and
+/// kinds listed in `KIND_SCHEMAS` get their port and server address checked.
+pub fn validate_json_config(name: &str, content: &Value) -> Vec<JsonFieldError> {
+    let mut errors = Vec::new();
+    if !content.is_object() {
+        errors.push(JsonFieldError::new("", "config must be a JSON object"));
+        return errors;
+    }
+    let kind = detect_kind(name);
+    let Some(schema) = KIND_SCHEMAS.iter().find(|schema| schema.kind == kind) else {
+        return errors;
+    };
+
+    let mut has_address = false;
+    walk_fields(content, "", &mut |path, key, value| {
+        let key = key.to_lowercase();
+        if schema.port_keys.contains(&key.as_str()) && !is_port(value) {
+            errors.push(JsonFieldError::new(path, "port must be a number between 0 and 65535"));
+        }
+        if SERVER_ADDRESS_KEYS.contains(&key.as_str()) && value.as_str().is_some_and(|s| !s.trim().is_empty()) {
+            has_address = true;
+        }
+    });
+
+    if schema.needs_address && !has_address {
+        errors.push(JsonFieldError::new("", "server config needs a host, server, url or address field"));
+    }
+    errors
+}
+
+pub(crate) fn is_allowed_json(name: &str) -> bool {
     let lower = name.to_lowercase();
     lower.starts_with("config_") && lower.ends_with(".json")
 }
@@ -95,13 +179,106 @@ pub fn load_json_config_for_active(name: &str) -> Result<Value, ConfigError> {
     Ok(value)
 }
 
-pub fn save_json_config_for_active(name: &str, content: &Value) -> Result<(), ConfigError> {
+/// Deep-merges `overlay` into `base`: objects merge key by key, anything else in
+/// `overlay` replaces the base value.
+pub fn merge_json(base: &Value, overlay: &Value) -> Value {
+    match (base, overlay) {
+        (Value::Object(base_map), Value::Object(overlay_map)) => {
+            let mut merged = base_map.clone();
+            for (key, value) in overlay_map {
+                let next = if let Some(existing) = merged.get(key) {
+                    merge_json(existing, value)
+                } else {
+                    value.clone()
+                };
+                merged.insert(key.clone(), next);
+            }
+            Value::Object(merged)
+        }
+        (_, overlay_value) => overlay_value.clone(),
+    }
+}
+
+/// Result of `patch_json_config_for_active`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonPatchResult {
+    /// The merged document as saved.
+    pub content: Value,
+    /// Warnings from `save_json_config_for_active`.
+    pub warnings: Vec<JsonFieldError>,
+}
+
+/// Deep-merges `patch` into the file and saves it through `save_json_config_for_active`.
+/// Lets one field change without resending the whole file.
+pub fn patch_json_config_for_active(name: &str, patch: &Value) -> Result<JsonPatchResult, ConfigError> {
+    let current = load_json_config_for_active(name)?;
+    let merged = merge_json(&current, patch);
+    let warnings = save_json_config_for_active(name, &merged)?;
+    Ok(JsonPatchResult {
+        content: merged,
+        warnings,
+    })
+}
+
+/// Saves after `validate_json_config`. Errors block the save for known kinds; for `other`
+/// files, whose layout is unknown, they are returned as warnings instead.
+pub fn save_json_config_for_active(name: &str, content: &Value) -> Result<Vec<JsonFieldError>, ConfigError> {
     let dir = active_game_dir()?;
     let path = path_for_file(&dir, name)?;
+    let errors = validate_json_config(name, content);
+    if !errors.is_empty() && detect_kind(name) != "other" {
+        let details: Vec<String> = errors
+            .iter()
+            .map(|e| if e.field.is_empty() { e.message.clone() } else { format!("{}: {}", e.field, e.message) })
+            .collect();
+        return Err(ConfigError::Parse(details.join("; ")));
+    }
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
+    if path.exists() {
+        fs::copy(&path, backup_path(&path))?;
+    }
     let pretty = serde_json::to_string_pretty(content)?;
     fs::write(path, pretty)?;
-    Ok(())
+    Ok(errors)
+}
+
+/// `config_x.json.bak`: the version a save replaced. Only one previous version is kept.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Puts back the version of `name` that the last save replaced and returns it.
+pub fn restore_json_config_for_active(name: &str) -> Result<Value, ConfigError> {
+    let dir = active_game_dir()?;
+    let path = path_for_file(&dir, name)?;
+    let backup = backup_path(&path);
+    if !backup.exists() {
+        return Err(ConfigError::NotFound(format!("No backup for {}", name)));
+    }
+    let content = fs::read_to_string(&backup)?;
+    let value: Value = serde_json::from_str(&content)?;
+    fs::rename(&backup, &path)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ports_are_checked_only_for_server_configs() {
+        let server = json!({ "host": "127.0.0.1", "port": "80", "backup": { "port": 0 } });
+        assert!(validate_json_config("config_server.json", &server).is_empty());
+
+        let errors = validate_json_config("config_server.json", &json!({ "host": "a", "port": "http" }));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "port");
+
+        assert!(validate_json_config("config_common.json", &json!({ "port": "http" })).is_empty());
+    }
 }
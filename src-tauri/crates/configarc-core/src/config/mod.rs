@@ -1,14 +1,18 @@
 use crate::error::ConfigError;
+use crate::trusted::sha256_hex;
 use configparser::ini::Ini;
+use serde::Serialize;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::collections::HashSet;
 
 pub mod paths;
 pub mod profiles;
+pub mod network_profiles;
 pub mod segatools;
 pub mod templates;
 pub mod json_configs;
+pub mod bundle;
 
 pub use segatools::SegatoolsConfig;
 
@@ -69,12 +73,28 @@ impl ConfigWriter for Ini {
 
 struct IniUpdater {
     lines: Vec<String>,
+    /// Sections that did not exist in the original content, lowercased.
+    created_sections: HashSet<String>,
+    /// The line ending most lines of the original content used.
+    line_ending: &'static str,
+    /// Whether the original content ended with a line break.
+    trailing_newline: bool,
+}
+
+/// The line ending most lines of `content` use.
+fn detect_line_ending(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    if crlf > lf { "\r\n" } else { "\n" }
 }
 
 impl IniUpdater {
     fn new(content: &str) -> Self {
         Self {
             lines: content.lines().map(|s| s.to_string()).collect(),
+            created_sections: HashSet::new(),
+            line_ending: detect_line_ending(content),
+            trailing_newline: content.ends_with('\n'),
         }
     }
 
@@ -88,41 +108,51 @@ impl IniUpdater {
         None
     }
 
-    fn set(&mut self, section: &str, key: &str, value: &str) {
+    /// Adds `line` at the end of `section`, creating the section if needed.
+    fn push_to_section(&mut self, section: &str, line: String) {
         if let Some(section_idx) = self.find_section_line(section) {
             let mut insert_idx = section_idx + 1;
-            let mut found = false;
-            
+            for i in (section_idx + 1)..self.lines.len() {
+                let trimmed = self.lines[i].trim();
+                if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                    break;
+                }
+                insert_idx = i + 1;
+            }
+            self.lines.insert(insert_idx, line);
+        } else {
+            if !self.lines.is_empty() && !self.lines.last().unwrap().trim().is_empty() {
+                self.lines.push("".to_string());
+            }
+            self.lines.push(format!("[{}]", section));
+            self.lines.push(line);
+            self.created_sections.insert(section.to_lowercase());
+        }
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: &str) {
+        if let Some(section_idx) = self.find_section_line(section) {
             for i in (section_idx + 1)..self.lines.len() {
                 let line = &self.lines[i];
                 let trimmed = line.trim();
                 if trimmed.starts_with('[') && trimmed.ends_with(']') {
-                    insert_idx = i;
                     break;
                 }
-                
+
                 if let Some((k, _)) = parse_line_key(line) {
                     if k.eq_ignore_ascii_case(key) {
                         self.lines[i] = format!("{}={}", key, value);
-                        found = true;
-                        break;
+                        return;
                     }
                 }
-                insert_idx = i + 1;
-            }
-            
-            if !found {
-                self.lines.insert(insert_idx, format!("{}={}", key, value));
             }
-        } else {
-            if !self.lines.is_empty() && !self.lines.last().unwrap().trim().is_empty() {
-                self.lines.push("".to_string());
-            }
-            self.lines.push(format!("[{}]", section));
-            self.lines.push(format!("{}={}", key, value));
         }
+        self.push_to_section(section, format!("{}={}", key, value));
     }
 
+    /// Comments out `key` in `section`. When the key is absent and the section is new,
+    /// the commented line the segatools templates show for it is added instead, so
+    /// e.g. a fresh `[slider]` keeps its `;cell1=0x53` scaffold.
     fn comment_out(&mut self, section: &str, key: &str) {
         if let Some(section_idx) = self.find_section_line(section) {
              for i in (section_idx + 1)..self.lines.len() {
@@ -141,11 +171,21 @@ impl IniUpdater {
                     }
                 }
             }
+            if !self.created_sections.contains(&section.to_lowercase()) {
+                return;
+            }
+        }
+        if let Some(placeholder) = template_placeholder(section, key) {
+            self.push_to_section(section, placeholder);
         }
     }
     
     fn to_string(&self) -> String {
-        self.lines.join("\n")
+        let mut out = self.lines.join(self.line_ending);
+        if self.trailing_newline && !self.lines.is_empty() {
+            out.push_str(self.line_ending);
+        }
+        out
     }
 }
 
@@ -158,6 +198,45 @@ impl ConfigWriter for IniUpdater {
     }
 }
 
+/// The commented-out `;key=value` line the templates use for `key` in `section`. Keys
+/// the templates elide with `; ... etc ...` (e.g. `cell3`) borrow the value of a
+/// commented sibling with the same name prefix.
+fn template_placeholder(section: &str, key: &str) -> Option<String> {
+  let family = key.trim_end_matches(|c: char| c.is_ascii_digit());
+  let mut sibling_value = None;
+  for template in [templates::CHUSAN_TEMPLATE, templates::MAI2_TEMPLATE, templates::MU3_TEMPLATE] {
+    let mut in_section = false;
+    for line in template.lines() {
+      let trimmed = line.trim();
+      if trimmed.starts_with('[') && trimmed.ends_with(']') {
+        in_section = trimmed[1..trimmed.len() - 1].trim().eq_ignore_ascii_case(section);
+        continue;
+      }
+      if !in_section {
+        continue;
+      }
+      let Some(entry) = trimmed.strip_prefix(';') else {
+        continue;
+      };
+      let Some((k, v)) = entry.split_once('=') else {
+        continue;
+      };
+      let k = k.trim();
+      if k.contains(char::is_whitespace) {
+        continue;
+      }
+      if k.eq_ignore_ascii_case(key) {
+        return Some(format!(";{}={}", key, v.trim()));
+      }
+      let k_family = k.trim_end_matches(|c: char| c.is_ascii_digit());
+      if sibling_value.is_none() && family.len() < key.len() && k_family.eq_ignore_ascii_case(family) {
+        sibling_value = Some(v.trim().to_string());
+      }
+    }
+  }
+  sibling_value.map(|v| format!(";{}={}", key, v))
+}
+
 fn parse_line_key(line: &str) -> Option<(String, bool)> {
     let trimmed = line.trim();
     if trimmed.is_empty() { return None; }
@@ -216,7 +295,12 @@ fn prune_existing_content(content: &str, cfg: &SegatoolsConfig) -> String {
     lines.push(line.to_string());
   }
 
-  lines.join("\n")
+  let line_ending = detect_line_ending(content);
+  let mut pruned = lines.join(line_ending);
+  if content.ends_with('\n') {
+    pruned.push_str(line_ending);
+  }
+  pruned
 }
 
 fn should_write_key(present_keys: &[String], section: &str, key: &str) -> bool {
@@ -604,77 +688,179 @@ fn perform_save(writer: &mut dyn ConfigWriter, cfg: &SegatoolsConfig) {
   }
 
   if should_save("slider") {
+    let keys: Vec<String> = (1..=32).map(|i| format!("cell{}", i)).collect();
     let mut vec = vec![("enable", bool_to_string(cfg.slider.enable))];
-    vec.push(("cell1", cfg.slider.cell1.to_string()));
-    vec.push(("cell2", cfg.slider.cell2.to_string()));
-    vec.push(("cell3", cfg.slider.cell3.to_string()));
-    vec.push(("cell4", cfg.slider.cell4.to_string()));
-    vec.push(("cell5", cfg.slider.cell5.to_string()));
-    vec.push(("cell6", cfg.slider.cell6.to_string()));
-    vec.push(("cell7", cfg.slider.cell7.to_string()));
-    vec.push(("cell8", cfg.slider.cell8.to_string()));
-    vec.push(("cell9", cfg.slider.cell9.to_string()));
-    vec.push(("cell10", cfg.slider.cell10.to_string()));
-    vec.push(("cell11", cfg.slider.cell11.to_string()));
-    vec.push(("cell12", cfg.slider.cell12.to_string()));
-    vec.push(("cell13", cfg.slider.cell13.to_string()));
-    vec.push(("cell14", cfg.slider.cell14.to_string()));
-    vec.push(("cell15", cfg.slider.cell15.to_string()));
-    vec.push(("cell16", cfg.slider.cell16.to_string()));
-    vec.push(("cell17", cfg.slider.cell17.to_string()));
-    vec.push(("cell18", cfg.slider.cell18.to_string()));
-    vec.push(("cell19", cfg.slider.cell19.to_string()));
-    vec.push(("cell20", cfg.slider.cell20.to_string()));
-    vec.push(("cell21", cfg.slider.cell21.to_string()));
-    vec.push(("cell22", cfg.slider.cell22.to_string()));
-    vec.push(("cell23", cfg.slider.cell23.to_string()));
-    vec.push(("cell24", cfg.slider.cell24.to_string()));
-    vec.push(("cell25", cfg.slider.cell25.to_string()));
-    vec.push(("cell26", cfg.slider.cell26.to_string()));
-    vec.push(("cell27", cfg.slider.cell27.to_string()));
-    vec.push(("cell28", cfg.slider.cell28.to_string()));
-    vec.push(("cell29", cfg.slider.cell29.to_string()));
-    vec.push(("cell30", cfg.slider.cell30.to_string()));
-    vec.push(("cell31", cfg.slider.cell31.to_string()));
-    vec.push(("cell32", cfg.slider.cell32.to_string()));
+    for (key, cell) in keys.iter().zip(cfg.slider.cells()) {
+      vec.push((key.as_str(), cell.to_string()));
+    }
     save_helper("slider", vec);
   }
 
   if should_save("ir") {
-    save_helper("ir",
-      vec![
-        ("ir1", cfg.ir.ir1.to_string()),
-        ("ir2", cfg.ir.ir2.to_string()),
-        ("ir3", cfg.ir.ir3.to_string()),
-        ("ir4", cfg.ir.ir4.to_string()),
-        ("ir5", cfg.ir.ir5.to_string()),
-        ("ir6", cfg.ir.ir6.to_string()),
-      ],
-    );
+    let keys: Vec<String> = (1..=6).map(|i| format!("ir{}", i)).collect();
+    let vec = keys
+      .iter()
+      .zip(cfg.ir.irs())
+      .map(|(key, ir)| (key.as_str(), ir.to_string()))
+      .collect();
+    save_helper("ir", vec);
   }
 
 }
 
-pub fn save_segatoools_config(path: &Path, cfg: &SegatoolsConfig) -> Result<(), ConfigError> {
+/// Writes `cfg` into the ini at `path`, keeping its comments and layout.
+///
+/// With `prune`, keys in managed sections (`present_sections`) that are not listed in
+/// `present_keys` are removed, so the file matches what the editor showed. Without it,
+/// such keys, e.g. ones added by hand to `[gfx]`, are kept untouched; the catch is that
+/// keys deleted in the editor stay in the file as well.
+pub fn save_segatoools_config(path: &Path, cfg: &SegatoolsConfig, prune: bool) -> Result<(), ConfigError> {
   if let Some(dir) = path.parent() {
     fs::create_dir_all(dir)?;
   }
 
+  let content = if path.exists() {
+    fs::read_to_string(path).map_err(ConfigError::Io)?
+  } else {
+    String::new()
+  };
+  fs::write(path, render_segatoools_save(cfg, &content, prune)).map_err(ConfigError::Io)?;
+  record_segatoools_hash(path);
+  Ok(())
+}
+
+/// What `save_segatoools_config` writes for `cfg` over the current file `content`. Without
+/// `present_sections` (a config not loaded from a file) the ini is written from scratch.
+fn render_segatoools_save(cfg: &SegatoolsConfig, content: &str, prune: bool) -> String {
   if cfg.present_sections.is_empty() {
-      let mut ini = Ini::new();
-      perform_save(&mut ini, cfg);
-      ini.write(path.to_string_lossy().as_ref()).map_err(ConfigError::Io)?;
+    let mut ini = Ini::new();
+    perform_save(&mut ini, cfg);
+    return ini.writes();
+  }
+  let content = if prune {
+    prune_existing_content(content, cfg)
   } else {
-      let content = if path.exists() {
-          fs::read_to_string(path).map_err(ConfigError::Io)?
-      } else {
-          String::new()
-      };
-      let content = prune_existing_content(&content, cfg);
-      let mut updater = IniUpdater::new(&content);
-      perform_save(&mut updater, cfg);
-      fs::write(path, updater.to_string()).map_err(ConfigError::Io)?;
+    content.to_string()
+  };
+  let mut updater = IniUpdater::new(&content);
+  perform_save(&mut updater, cfg);
+  updater.to_string()
+}
+
+/// `segatools.ini.sha256` beside the ini: the hash of the content IRIS last wrote.
+fn segatoools_hash_path(path: &Path) -> PathBuf {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(".sha256");
+  path.with_file_name(name)
+}
+
+/// Remembers the current content of `path` as written by IRIS, so a later external edit
+/// can be told apart from our own saves. Best effort; a failure only loses that signal.
+pub(crate) fn record_segatoools_hash(path: &Path) {
+  if let Ok(data) = fs::read(path) {
+    let _ = fs::write(segatoools_hash_path(path), sha256_hex(&data));
   }
+}
+
+/// Whether `content` differs from what IRIS last wrote to `path`; `None` when IRIS has
+/// not recorded a save of it yet.
+pub fn segatoools_edited_externally(path: &Path, content: &[u8]) -> Option<bool> {
+  let recorded = fs::read_to_string(segatoools_hash_path(path)).ok()?;
+  Some(!recorded.trim().eq_ignore_ascii_case(&sha256_hex(content)))
+}
+
+/// Replaces one section of `cfg` with its defaults. `section` is the ini section name.
+pub fn reset_section(cfg: &mut SegatoolsConfig, section: &str) -> Result<(), ConfigError> {
+  match section.trim().to_lowercase().as_str() {
+    "aimeio" => cfg.aimeio = Default::default(),
+    "aime" => cfg.aime = Default::default(),
+    "vfd" => cfg.vfd = Default::default(),
+    "amvideo" => cfg.amvideo = Default::default(),
+    "clock" => cfg.clock = Default::default(),
+    "dns" => cfg.dns = Default::default(),
+    "ds" => cfg.ds = Default::default(),
+    "eeprom" => cfg.eeprom = Default::default(),
+    "gpio" => cfg.gpio = Default::default(),
+    "gfx" => cfg.gfx = Default::default(),
+    "hwmon" => cfg.hwmon = Default::default(),
+    "jvs" => cfg.jvs = Default::default(),
+    "io4" => cfg.io4 = Default::default(),
+    "keychip" => cfg.keychip = Default::default(),
+    "netenv" => cfg.netenv = Default::default(),
+    "pcbid" => cfg.pcbid = Default::default(),
+    "sram" => cfg.sram = Default::default(),
+    "vfs" => cfg.vfs = Default::default(),
+    "epay" => cfg.epay = Default::default(),
+    "openssl" => cfg.openssl = Default::default(),
+    "system" => cfg.system = Default::default(),
+    "led15070" => cfg.led15070 = Default::default(),
+    "unity" => cfg.unity = Default::default(),
+    "mai2io" => cfg.mai2io = Default::default(),
+    "chuniio" => cfg.chuniio = Default::default(),
+    "mu3io" => cfg.mu3io = Default::default(),
+    "button" => cfg.button = Default::default(),
+    "touch" => cfg.touch = Default::default(),
+    "led15093" => cfg.led15093 = Default::default(),
+    "led" => cfg.led = Default::default(),
+    "io3" => cfg.io3 = Default::default(),
+    "slider" => cfg.slider = Default::default(),
+    "ir" => cfg.ir = Default::default(),
+    other => return Err(ConfigError::NotFound(format!("Unknown segatools section: {}", other))),
+  }
+  Ok(())
+}
+
+/// Sets the `enable` flag of one section of `cfg`. `section` is the ini section name;
+/// sections without an `enable` key, such as `io3` or `ir`, are an error.
+pub fn set_section_enabled(cfg: &mut SegatoolsConfig, section: &str, enabled: bool) -> Result<(), ConfigError> {
+  let section = section.trim().to_lowercase();
+  let flag = match section.as_str() {
+    "aime" => &mut cfg.aime.enable,
+    "vfd" => &mut cfg.vfd.enable,
+    "amvideo" => &mut cfg.amvideo.enable,
+    "ds" => &mut cfg.ds.enable,
+    "eeprom" => &mut cfg.eeprom.enable,
+    "gpio" => &mut cfg.gpio.enable,
+    "gfx" => &mut cfg.gfx.enable,
+    "hwmon" => &mut cfg.hwmon.enable,
+    "jvs" => &mut cfg.jvs.enable,
+    "io4" => &mut cfg.io4.enable,
+    "keychip" => &mut cfg.keychip.enable,
+    "netenv" => &mut cfg.netenv.enable,
+    "pcbid" => &mut cfg.pcbid.enable,
+    "sram" => &mut cfg.sram.enable,
+    "vfs" => &mut cfg.vfs.enable,
+    "epay" => &mut cfg.epay.enable,
+    "openssl" => &mut cfg.openssl.enable,
+    "system" => &mut cfg.system.enable,
+    "led15070" => &mut cfg.led15070.enable,
+    "unity" => &mut cfg.unity.enable,
+    "button" => &mut cfg.button.enable,
+    "led15093" => &mut cfg.led15093.enable,
+    "slider" => &mut cfg.slider.enable,
+    "aimeio" | "clock" | "dns" | "mai2io" | "chuniio" | "mu3io" | "touch" | "led" | "io3" | "ir" => {
+      return Err(ConfigError::NotFound(format!("Segatools section {} has no enable flag", section)));
+    }
+    other => return Err(ConfigError::NotFound(format!("Unknown segatools section: {}", other))),
+  };
+  *flag = enabled;
+  Ok(())
+}
+
+/// Sets individual `(section, key, value)` entries in an existing ini file in place,
+/// leaving every other line untouched. Unlike `save_segatoools_config` this never prunes.
+pub(crate) fn set_segatoools_values(path: &Path, values: &[(&str, &str, String)]) -> Result<(), ConfigError> {
+  let content = if path.exists() {
+    fs::read_to_string(path)?
+  } else {
+    String::new()
+  };
+  let mut updater = IniUpdater::new(&content);
+  for (section, key, value) in values {
+    updater.set(section, key, value);
+  }
+  fs::write(path, updater.to_string())?;
+  record_segatoools_hash(path);
   Ok(())
 }
 
@@ -685,9 +871,60 @@ pub fn render_segatoools_config(cfg: &SegatoolsConfig, existing_content: Option<
   Ok(updater.to_string())
 }
 
-pub fn load_segatoools_config_from_string(content: &str) -> Result<SegatoolsConfig, ConfigError> {
+/// Whether `save_segatoools_config(path, cfg, prune)` would write different bytes than are
+/// on disk. A missing file always counts as a change.
+pub fn segatoools_save_would_change(path: &Path, cfg: &SegatoolsConfig, prune: bool) -> Result<bool, ConfigError> {
+  if !path.exists() {
+    return Ok(true);
+  }
+  let existing = fs::read_to_string(path)?;
+  Ok(render_segatoools_save(cfg, &existing, prune) != existing)
+}
+
+/// Reads ini `content` by the same line rules `IniUpdater` writes with: only `[...]` lines
+/// are headers and a value runs from the first `=`, so `authdataPath=DEVICE\a=b.bin`
+/// keeps its second `=`. A `;`/`#` starts a comment at the start of a line or after
+/// whitespace (`left1=0x41  ; A`); elsewhere it is part of the value.
+fn parse_ini(content: &str) -> Ini {
   let mut parser = Ini::new();
-  parser.read(content.to_string()).map_err(|e| ConfigError::Parse(e))?;
+  let mut section = "default".to_string();
+  for line in content.lines() {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with(';') || trimmed.starts_with('#') {
+      continue;
+    }
+    if trimmed.starts_with('[') && trimmed.ends_with(']') {
+      section = trimmed[1..trimmed.len() - 1].trim().to_lowercase();
+      continue;
+    }
+    match trimmed.split_once('=') {
+      Some((key, value)) => {
+        let key = key.trim();
+        if !key.is_empty() {
+          parser.set(&section, key, Some(strip_inline_comment(value).trim().to_string()));
+        }
+      }
+      None => {
+        parser.set(&section, trimmed, None);
+      }
+    }
+  }
+  parser
+}
+
+fn strip_inline_comment(value: &str) -> &str {
+  let mut prev_space = false;
+  for (idx, ch) in value.char_indices() {
+    if prev_space && (ch == ';' || ch == '#') {
+      return &value[..idx];
+    }
+    prev_space = ch.is_whitespace();
+  }
+  value
+}
+
+pub fn load_segatoools_config_from_string(content: &str) -> Result<SegatoolsConfig, ConfigError> {
+  let parser = parse_ini(content);
 
   let mut cfg = SegatoolsConfig::default();
 
@@ -924,49 +1161,50 @@ pub fn load_segatoools_config_from_string(content: &str) -> Result<SegatoolsConf
   cfg.io3.ir = read_u32(&parser, "io3", "ir", cfg.io3.ir);
 
   cfg.slider.enable = read_bool(&parser, "slider", "enable", cfg.slider.enable);
-  cfg.slider.cell1 = read_u32(&parser, "slider", "cell1", cfg.slider.cell1);
-  cfg.slider.cell2 = read_u32(&parser, "slider", "cell2", cfg.slider.cell2);
-  cfg.slider.cell3 = read_u32(&parser, "slider", "cell3", cfg.slider.cell3);
-  cfg.slider.cell4 = read_u32(&parser, "slider", "cell4", cfg.slider.cell4);
-  cfg.slider.cell5 = read_u32(&parser, "slider", "cell5", cfg.slider.cell5);
-  cfg.slider.cell6 = read_u32(&parser, "slider", "cell6", cfg.slider.cell6);
-  cfg.slider.cell7 = read_u32(&parser, "slider", "cell7", cfg.slider.cell7);
-  cfg.slider.cell8 = read_u32(&parser, "slider", "cell8", cfg.slider.cell8);
-  cfg.slider.cell9 = read_u32(&parser, "slider", "cell9", cfg.slider.cell9);
-  cfg.slider.cell10 = read_u32(&parser, "slider", "cell10", cfg.slider.cell10);
-  cfg.slider.cell11 = read_u32(&parser, "slider", "cell11", cfg.slider.cell11);
-  cfg.slider.cell12 = read_u32(&parser, "slider", "cell12", cfg.slider.cell12);
-  cfg.slider.cell13 = read_u32(&parser, "slider", "cell13", cfg.slider.cell13);
-  cfg.slider.cell14 = read_u32(&parser, "slider", "cell14", cfg.slider.cell14);
-  cfg.slider.cell15 = read_u32(&parser, "slider", "cell15", cfg.slider.cell15);
-  cfg.slider.cell16 = read_u32(&parser, "slider", "cell16", cfg.slider.cell16);
-  cfg.slider.cell17 = read_u32(&parser, "slider", "cell17", cfg.slider.cell17);
-  cfg.slider.cell18 = read_u32(&parser, "slider", "cell18", cfg.slider.cell18);
-  cfg.slider.cell19 = read_u32(&parser, "slider", "cell19", cfg.slider.cell19);
-  cfg.slider.cell20 = read_u32(&parser, "slider", "cell20", cfg.slider.cell20);
-  cfg.slider.cell21 = read_u32(&parser, "slider", "cell21", cfg.slider.cell21);
-  cfg.slider.cell22 = read_u32(&parser, "slider", "cell22", cfg.slider.cell22);
-  cfg.slider.cell23 = read_u32(&parser, "slider", "cell23", cfg.slider.cell23);
-  cfg.slider.cell24 = read_u32(&parser, "slider", "cell24", cfg.slider.cell24);
-  cfg.slider.cell25 = read_u32(&parser, "slider", "cell25", cfg.slider.cell25);
-  cfg.slider.cell26 = read_u32(&parser, "slider", "cell26", cfg.slider.cell26);
-  cfg.slider.cell27 = read_u32(&parser, "slider", "cell27", cfg.slider.cell27);
-  cfg.slider.cell28 = read_u32(&parser, "slider", "cell28", cfg.slider.cell28);
-  cfg.slider.cell29 = read_u32(&parser, "slider", "cell29", cfg.slider.cell29);
-  cfg.slider.cell30 = read_u32(&parser, "slider", "cell30", cfg.slider.cell30);
-  cfg.slider.cell31 = read_u32(&parser, "slider", "cell31", cfg.slider.cell31);
-  cfg.slider.cell32 = read_u32(&parser, "slider", "cell32", cfg.slider.cell32);
-
-  cfg.ir.ir1 = read_u32(&parser, "ir", "ir1", cfg.ir.ir1);
-  cfg.ir.ir2 = read_u32(&parser, "ir", "ir2", cfg.ir.ir2);
-  cfg.ir.ir3 = read_u32(&parser, "ir", "ir3", cfg.ir.ir3);
-  cfg.ir.ir4 = read_u32(&parser, "ir", "ir4", cfg.ir.ir4);
-  cfg.ir.ir5 = read_u32(&parser, "ir", "ir5", cfg.ir.ir5);
-  cfg.ir.ir6 = read_u32(&parser, "ir", "ir6", cfg.ir.ir6);
+  let mut cells = cfg.slider.cells();
+  for (i, cell) in cells.iter_mut().enumerate() {
+    *cell = read_u32(&parser, "slider", &format!("cell{}", i + 1), *cell);
+  }
+  cfg.slider.set_cells(cells);
+
+  let mut irs = cfg.ir.irs();
+  for (i, ir) in irs.iter_mut().enumerate() {
+    *ir = read_u32(&parser, "ir", &format!("ir{}", i + 1), *ir);
+  }
+  cfg.ir.set_irs(irs);
 
   Ok(cfg)
 }
 
+/// Which sections and keys of an ini file `save_segatoools_config` treats as managed.
+/// Keys missing from `present_keys` are dropped from managed sections on save.
+#[derive(Debug, Clone, Serialize)]
+pub struct SegatoolsIniInspection {
+  pub present_sections: Vec<String>,
+  pub present_keys: Vec<String>,
+  pub commented_keys: Vec<String>,
+  pub content: String,
+  /// SHA-256 of `content`.
+  pub config_hash: String,
+  /// True when the file changed since IRIS last saved it, e.g. another tool rewrote it.
+  pub edited_externally: bool,
+}
+
+pub fn inspect_segatoools_ini(path: &Path) -> Result<SegatoolsIniInspection, ConfigError> {
+  let content = fs::read_to_string(path).map_err(ConfigError::Io)?;
+  let cfg = load_segatoools_config_from_string(&content)?;
+  let mut present_sections = cfg.present_sections;
+  present_sections.sort();
+  Ok(SegatoolsIniInspection {
+    present_sections,
+    present_keys: cfg.present_keys,
+    commented_keys: cfg.commented_keys,
+    config_hash: sha256_hex(content.as_bytes()),
+    edited_externally: segatoools_edited_externally(path, content.as_bytes()).unwrap_or(false),
+    content,
+  })
+}
+
 pub fn load_segatoools_config(path: &Path) -> Result<SegatoolsConfig, ConfigError> {
   let content = fs::read_to_string(path).map_err(ConfigError::Io)?;
   load_segatoools_config_from_string(&content)
@@ -975,3 +1213,67 @@ pub fn load_segatoools_config(path: &Path) -> Result<SegatoolsConfig, ConfigErro
 pub fn default_segatoools_config() -> SegatoolsConfig {
   SegatoolsConfig::default()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_ini_keeps_equals_in_values() {
+    let cfg = load_segatoools_config_from_string("[aime]\nauthdataPath=DEVICE\\a=b.bin\n").unwrap();
+    assert_eq!(cfg.aime.authdata_path, "DEVICE\\a=b.bin");
+  }
+
+  #[test]
+  fn parse_ini_strips_inline_comments() {
+    let content = "[aime]\nportNo=12  ; COM12\naimePath=DEVICE\\aime.txt # card file\nfelicaPath=DEVICE\\a;b#c.txt\n";
+    let cfg = load_segatoools_config_from_string(content).unwrap();
+    assert_eq!(cfg.aime.port_no, 12);
+    assert_eq!(cfg.aime.aime_path, "DEVICE\\aime.txt");
+    assert_eq!(cfg.aime.felica_path, "DEVICE\\a;b#c.txt");
+  }
+
+  #[test]
+  fn template_placeholder_uses_commented_cells() {
+    assert_eq!(template_placeholder("slider", "cell1").as_deref(), Some(";cell1=0x53"));
+    assert_eq!(template_placeholder("slider", "cell5").as_deref(), Some(";cell5=0x53"));
+    assert_eq!(template_placeholder("ir", "ir3").as_deref(), Some(";ir3=0x53"));
+    assert_eq!(template_placeholder("slider", "bogus"), None);
+    assert_eq!(template_placeholder("nosuchsection", "cell1"), None);
+  }
+
+  #[test]
+  fn save_keeps_crlf_line_endings() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("segatools.ini");
+    fs::write(&path, "[aime]\r\nenable=1\r\nportNo=12\r\n\r\n[vfd]\r\nenable=1\r\n").unwrap();
+
+    let mut cfg = load_segatoools_config(&path).unwrap();
+    cfg.aime.port_no = 3;
+    save_segatoools_config(&path, &cfg, true).unwrap();
+
+    let saved = fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("portNo=3\r\n"));
+    assert_eq!(saved.matches('\n').count(), saved.matches("\r\n").count());
+  }
+
+  #[test]
+  fn unchanged_save_is_byte_identical() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("segatools.ini");
+    let content = "; Chunithm\n[vfs]\namfs=amfs\noption=option\nappdata=appdata\n\n[aime]\n; Card reader\nenable=1\naimePath=DEVICE\\aime.txt\n\n[keychip]\nid=A69E-01A88888888\n";
+    for original in [
+      content.to_string(),
+      content.trim_end().to_string(),
+      content.replace('\n', "\r\n"),
+    ] {
+      fs::write(&path, &original).unwrap();
+      let cfg = load_segatoools_config(&path).unwrap();
+      for prune in [false, true] {
+        assert!(!segatoools_save_would_change(&path, &cfg, prune).unwrap());
+        save_segatoools_config(&path, &cfg, prune).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+      }
+    }
+  }
+}
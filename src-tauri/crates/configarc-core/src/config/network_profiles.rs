@@ -0,0 +1,108 @@
+use super::paths::{profiles_dir_for_active, profiles_dir_for_game, segatoools_path_for_game_id};
+use super::{load_segatoools_config, set_segatoools_values, SegatoolsConfig};
+use crate::error::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Per-cabinet network identity for LAN setups. Only the fields that are set are applied,
+/// and applying never touches any other key in segatools.ini.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkProfile {
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub keychip_id: Option<String>,
+  #[serde(default)]
+  pub pcbid_serial_no: Option<String>,
+  #[serde(default)]
+  pub netenv_addr_suffix: Option<u32>,
+  #[serde(default)]
+  pub system_dipsw1: Option<bool>,
+}
+
+impl NetworkProfile {
+  /// Patches the profile's fields onto an in-memory config.
+  pub fn apply_to(&self, cfg: &mut SegatoolsConfig) {
+    if let Some(id) = &self.keychip_id {
+      cfg.keychip.id = id.clone();
+    }
+    if let Some(serial) = &self.pcbid_serial_no {
+      cfg.pcbid.serial_no = serial.clone();
+    }
+    if let Some(suffix) = self.netenv_addr_suffix {
+      cfg.netenv.addr_suffix = suffix;
+    }
+    if let Some(dipsw1) = self.system_dipsw1 {
+      cfg.system.dipsw1 = dipsw1;
+    }
+  }
+
+  fn ini_values(&self) -> Vec<(&'static str, &'static str, String)> {
+    let mut values = Vec::new();
+    if let Some(id) = &self.keychip_id {
+      values.push(("keychip", "id", id.clone()));
+    }
+    if let Some(serial) = &self.pcbid_serial_no {
+      values.push(("pcbid", "serialNo", serial.clone()));
+    }
+    if let Some(suffix) = self.netenv_addr_suffix {
+      values.push(("netenv", "addrSuffix", suffix.to_string()));
+    }
+    if let Some(dipsw1) = self.system_dipsw1 {
+      values.push(("system", "dipsw1", if dipsw1 { "1" } else { "0" }.to_string()));
+    }
+    values
+  }
+}
+
+fn network_profiles_path(game_id: Option<&str>) -> Result<std::path::PathBuf, ConfigError> {
+  let dir = match game_id {
+    Some(id) => profiles_dir_for_game(id)?,
+    None => profiles_dir_for_active()?,
+  };
+  Ok(dir.join("configarc_network_profiles.json"))
+}
+
+pub fn list_network_profiles(game_id: Option<&str>) -> Result<Vec<NetworkProfile>, ConfigError> {
+  let path = network_profiles_path(game_id)?;
+  if !path.exists() {
+    return Ok(vec![]);
+  }
+  let data = fs::read_to_string(&path)?;
+  if data.trim().is_empty() {
+    return Ok(vec![]);
+  }
+  let profiles: Vec<NetworkProfile> = serde_json::from_str(&data)?;
+  Ok(profiles)
+}
+
+pub fn save_network_profile(profile: &NetworkProfile, game_id: Option<&str>) -> Result<(), ConfigError> {
+  let mut profiles = list_network_profiles(game_id)?;
+  profiles.retain(|p| p.id != profile.id);
+  profiles.push(profile.clone());
+
+  let path = network_profiles_path(game_id)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let json = serde_json::to_string_pretty(&profiles)?;
+  fs::write(path, json)?;
+  Ok(())
+}
+
+/// Writes the profile's keys into the game's segatools.ini and returns the reloaded config.
+pub fn apply_network_profile(id: &str, game_id: &str) -> Result<SegatoolsConfig, ConfigError> {
+  let profile = list_network_profiles(Some(game_id))?
+    .into_iter()
+    .find(|p| p.id == id)
+    .ok_or_else(|| ConfigError::NotFound(format!("Network profile {}", id)))?;
+  let path = segatoools_path_for_game_id(game_id)?;
+  if !path.exists() {
+    return Err(ConfigError::NotFound(
+      "segatools.ini not found. Please deploy first.".to_string(),
+    ));
+  }
+  set_segatoools_values(&path, &profile.ini_values())?;
+  load_segatoools_config(&path)
+}
@@ -5,7 +5,34 @@ use std::env;
 use std::path::{Path, PathBuf};
 
 fn active_game_file() -> PathBuf {
-  Path::new(".").join("configarc_active_game.json")
+  app_data_file("configarc_active_game.json")
+}
+
+/// Resolves `name` next to the executable. Older builds kept these files in the
+/// working directory, so a copy found there is migrated the first time it is needed.
+pub(crate) fn app_data_file(name: &str) -> PathBuf {
+  let path = app_root_dir().join(name);
+  if !path.exists() {
+    let legacy = Path::new(".").join(name);
+    if legacy.is_file() {
+      let _ = fs::copy(&legacy, &path);
+    }
+  }
+  path
+}
+
+/// Writes through a sibling temp file and renames it over `path`, so a crash never
+/// leaves a truncated file behind.
+pub(crate) fn write_atomic(path: &Path, data: impl AsRef<[u8]>) -> std::io::Result<()> {
+  let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+  tmp_name.push(".tmp");
+  let tmp_path = path.with_file_name(tmp_name);
+  fs::write(&tmp_path, data)?;
+  if let Err(err) = fs::rename(&tmp_path, path) {
+    let _ = fs::remove_file(&tmp_path);
+    return Err(err);
+  }
+  Ok(())
 }
 
 pub fn get_active_game_id() -> Result<Option<String>, ConfigError> {
@@ -21,7 +48,7 @@ pub fn get_active_game_id() -> Result<Option<String>, ConfigError> {
 }
 
 pub fn set_active_game_id(id: &str) -> Result<(), ConfigError> {
-  fs::write(active_game_file(), id)?;
+  write_atomic(&active_game_file(), id)?;
   Ok(())
 }
 
@@ -41,7 +68,7 @@ pub fn active_game_dir() -> Result<PathBuf, ConfigError> {
   game_dir(&active)
 }
 
-fn app_root_dir() -> PathBuf {
+pub(crate) fn app_root_dir() -> PathBuf {
   std::env::current_exe()
     .ok()
     .and_then(|exe| exe.parent().map(|p| p.to_path_buf()))
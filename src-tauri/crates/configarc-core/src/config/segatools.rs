@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -296,6 +299,29 @@ impl Default for ClockConfig {
   }
 }
 
+impl ClockConfig {
+  /// Describes risky clock settings; empty when none apply.
+  ///
+  /// `writeable` lets the game set the Windows clock, and a wrong system time can corrupt
+  /// play and save data. It is worse together with `timezone`, since the game then writes
+  /// JST back as local time and shifts the clock by the UTC offset, and with `timewarp`,
+  /// since the warped time past the maintenance window gets written back to the clock.
+  pub fn validate(&self) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !self.writeable {
+      return warnings;
+    }
+    warnings.push("clock writeable lets the game change the system clock and can corrupt save data".to_string());
+    if self.timezone {
+      warnings.push("clock writeable with timezone forced writes JST as local time and shifts the system clock".to_string());
+    }
+    if self.timewarp {
+      warnings.push("clock writeable with timewarp writes the warped time back to the system clock".to_string());
+    }
+    warnings
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DnsConfig {
@@ -858,6 +884,36 @@ impl Default for SliderConfig {
   }
 }
 
+impl SliderConfig {
+  /// Slider cell key bindings in `cell1..cell32` order.
+  pub fn cells(&self) -> [u32; 32] {
+    [
+      self.cell1, self.cell2, self.cell3, self.cell4,
+      self.cell5, self.cell6, self.cell7, self.cell8,
+      self.cell9, self.cell10, self.cell11, self.cell12,
+      self.cell13, self.cell14, self.cell15, self.cell16,
+      self.cell17, self.cell18, self.cell19, self.cell20,
+      self.cell21, self.cell22, self.cell23, self.cell24,
+      self.cell25, self.cell26, self.cell27, self.cell28,
+      self.cell29, self.cell30, self.cell31, self.cell32,
+    ]
+  }
+
+  /// Assign all slider cell key bindings from an array in `cell1..cell32` order.
+  pub fn set_cells(&mut self, cells: [u32; 32]) {
+    [
+      self.cell1, self.cell2, self.cell3, self.cell4,
+      self.cell5, self.cell6, self.cell7, self.cell8,
+      self.cell9, self.cell10, self.cell11, self.cell12,
+      self.cell13, self.cell14, self.cell15, self.cell16,
+      self.cell17, self.cell18, self.cell19, self.cell20,
+      self.cell21, self.cell22, self.cell23, self.cell24,
+      self.cell25, self.cell26, self.cell27, self.cell28,
+      self.cell29, self.cell30, self.cell31, self.cell32,
+    ] = cells;
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IrConfig {
@@ -876,3 +932,76 @@ impl Default for IrConfig {
     }
   }
 }
+
+impl IrConfig {
+  /// IR sensor key bindings in `ir1..ir6` order.
+  pub fn irs(&self) -> [u32; 6] {
+    [self.ir1, self.ir2, self.ir3, self.ir4, self.ir5, self.ir6]
+  }
+
+  /// Assign all IR sensor key bindings from an array in `ir1..ir6` order.
+  pub fn set_irs(&mut self, irs: [u32; 6]) {
+    [self.ir1, self.ir2, self.ir3, self.ir4, self.ir5, self.ir6] = irs;
+  }
+}
+
+fn serial_suffix_ok(suffix: &[u8]) -> bool {
+  suffix.len() == 11
+    && (&suffix[..2] == b"01" || &suffix[..2] == b"20")
+    && b"ABCDU".contains(&suffix[2])
+    && suffix[3..].iter().all(u8::is_ascii_digit)
+}
+
+/// Keychip serials follow `A\d{2}(E|X)-(01|20)[ABCDU]\d{8}`, e.g. `A69E-01A88888888`.
+pub fn validate_keychip_serial(id: &str) -> bool {
+  let b = id.as_bytes();
+  b.len() == 16
+    && b[0] == b'A'
+    && b[1..3].iter().all(u8::is_ascii_digit)
+    && (b[3] == b'E' || b[3] == b'X')
+    && b[4] == b'-'
+    && serial_suffix_ok(&b[5..])
+}
+
+/// PCBIDs are ALLS MAIN IDs without the hyphen, e.g. `ACAE01A99999999`.
+pub fn validate_pcbid_serial(serial: &str) -> bool {
+  let b = serial.as_bytes();
+  b.len() == 15
+    && b[0] == b'A'
+    && b[1..3].iter().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    && (b[3] == b'E' || b[3] == b'X')
+    && serial_suffix_ok(&b[4..])
+}
+
+/// Aime access codes, as stored in `aime.txt`, are exactly 20 digits.
+pub fn validate_aime_access_code(code: &str) -> bool {
+  code.len() == 20 && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// A random 20-digit access code. It starts with `5` like physical Aime cards, so it never
+/// collides with Banapass (`3`) ranges.
+pub fn generate_aime_access_code() -> String {
+  // RandomState is seeded from the OS per process; mixing in the time varies each call.
+  let state = RandomState::new();
+  let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+  let mut code = String::from("5");
+  for round in 0..19u64 {
+    let mut hasher = state.build_hasher();
+    hasher.write_u128(nanos);
+    hasher.write_u64(round);
+    code.push(char::from(b'0' + (hasher.finish() % 10) as u8));
+  }
+  code
+}
+
+/// Describes every enabled serial that does not match its expected pattern.
+pub fn serial_problems(config: &SegatoolsConfig) -> Vec<String> {
+  let mut problems = Vec::new();
+  if config.keychip.enable && !validate_keychip_serial(&config.keychip.id) {
+    problems.push("keychip id must match A##E-01A########".to_string());
+  }
+  if config.pcbid.enable && !validate_pcbid_serial(&config.pcbid.serial_no) {
+    problems.push("pcbid serialNo must match A??E01A########".to_string());
+  }
+  problems
+}
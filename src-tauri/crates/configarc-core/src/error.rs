@@ -10,6 +10,20 @@ pub enum ConfigError {
     Json(#[from] serde_json::Error),
     #[error("Config not found: {0}")]
     NotFound(String),
+    #[error("Network error: {0}")]
+    Network(String),
+}
+
+impl ConfigError {
+    /// Stable machine-readable kind of the error, for the UI to branch on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConfigError::Io(_) => "io",
+            ConfigError::Parse(_) | ConfigError::Json(_) => "parse",
+            ConfigError::NotFound(_) => "not_found",
+            ConfigError::Network(_) => "network",
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -22,4 +36,10 @@ pub enum GameError {
     NotFound(String),
     #[error("Launch error: {0}")]
     Launch(String),
+    #[error("Executable not found: {0}")]
+    MissingExecutable(String),
+    #[error("Working directory is not a directory: {0}")]
+    InvalidWorkingDir(String),
+    #[error("Invalid game id: {0}")]
+    InvalidId(String),
 }
@@ -1,52 +1,214 @@
-use super::model::Game;
+use super::model::{Game, LaunchMode};
+use super::store;
 use crate::config::paths::segatools_root_for_game_id;
+use crate::config::segatools::SegatoolsConfig;
 use crate::error::GameError;
-use std::path::Path;
-use std::process::{Child, Command};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
 use std::fs;
+use std::io::{self, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 use std::os::windows::process::CommandExt;
 
 const CREATE_NEW_CONSOLE: u32 = 0x00000010;
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+/// Failed hook runs are appended here, in the game's segatools directory.
+const HOOK_LOG: &str = "launch_hooks.log";
+/// How long a launch waits for `pre_launch` before starting the game anyway. Short setup
+/// scripts finish first; long-running ones, such as a local server, keep running beside it.
+const PRE_LAUNCH_HOOK_WAIT: Duration = Duration::from_secs(5);
+
+/// Quotes one argument for a line of a `.bat` file so it reaches the program as a single
+/// argv entry: `%` is doubled, embedded quotes use the MSVCRT `\"` escape, and cmd.exe
+/// metacharacters that end up outside a quoted run are escaped with `^`.
+fn quote_batch_arg(arg: &str) -> String {
+  let needs_quotes = arg.is_empty()
+    || arg
+      .chars()
+      .any(|c| matches!(c, ' ' | '\t' | '"' | '&' | '|' | '<' | '>' | '^' | '(' | ')' | ',' | ';' | '='));
+
+  let mut quoted = String::new();
+  if needs_quotes {
+    quoted.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+      match c {
+        '\\' => backslashes += 1,
+        '"' => {
+          quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+          backslashes = 0;
+        }
+        _ => {
+          quoted.push_str(&"\\".repeat(backslashes));
+          backslashes = 0;
+        }
+      }
+      if c != '\\' {
+        quoted.push(c);
+      }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+  } else {
+    quoted.push_str(arg);
+  }
+
+  let mut escaped = String::with_capacity(quoted.len());
+  let mut in_quotes = false;
+  for c in quoted.chars() {
+    match c {
+      '"' => in_quotes = !in_quotes,
+      '%' => escaped.push('%'),
+      '&' | '|' | '<' | '>' | '^' | '(' | ')' if !in_quotes => escaped.push('^'),
+      _ => {}
+    }
+    escaped.push(c);
+  }
+  escaped
+}
+
+fn batch_args(args: &[String]) -> String {
+  args.iter().map(|arg| quote_batch_arg(arg)).collect::<Vec<_>>().join(" ")
+}
+
+/// The batch line that runs `target` through `inject` with `hook` loaded, passing `args`.
+fn inject_line(inject: &Path, hook: &Path, target: &str, args: &[String]) -> String {
+  format!(
+    "\"{}\" -d -k \"{}\" {} {}\r\n",
+    inject.to_string_lossy(),
+    hook.to_string_lossy(),
+    target,
+    batch_args(args)
+  )
+}
+
+const INJECT: &str = "inject.exe";
+const INJECT_X64: &str = "inject_x64.exe";
+const INJECT_X86: &str = "inject_x86.exe";
+const HOOK_CHUSAN_X64: &str = "chusanhook_x64.dll";
+const HOOK_CHUSAN_X86: &str = "chusanhook_x86.dll";
+const HOOK_MAI2: &str = "mai2hook.dll";
+const HOOK_MU3: &str = "mu3hook.dll";
+
+/// Which segatools launch files exist under a segatools root, checked once by `analyze`.
+pub struct GameLayout {
+  pub root: PathBuf,
+  pub inject: bool,
+  pub inject_x64: bool,
+  pub inject_x86: bool,
+  pub hook_chusan_x64: bool,
+  pub hook_chusan_x86: bool,
+  pub hook_mai2: bool,
+  pub hook_mu3: bool,
+}
+
+impl GameLayout {
+  pub fn analyze(root: &Path) -> GameLayout {
+    let has = |name: &str| root.join(name).exists();
+    GameLayout {
+      root: root.to_path_buf(),
+      inject: has(INJECT),
+      inject_x64: has(INJECT_X64),
+      inject_x86: has(INJECT_X86),
+      hook_chusan_x64: has(HOOK_CHUSAN_X64),
+      hook_chusan_x86: has(HOOK_CHUSAN_X86),
+      hook_mai2: has(HOOK_MAI2),
+      hook_mu3: has(HOOK_MU3),
+    }
+  }
+
+  pub fn path(&self, name: &str) -> PathBuf {
+    self.root.join(name)
+  }
+
+  pub fn has_inject(&self) -> bool {
+    self.inject || self.inject_x64 || self.inject_x86
+  }
+
+  /// 64-bit injector for chusan: `inject_x64.exe`, falling back to `inject.exe`.
+  fn chusan_inject_x64(&self) -> Option<&'static str> {
+    if self.inject_x64 {
+      Some(INJECT_X64)
+    } else if self.inject {
+      Some(INJECT)
+    } else {
+      None
+    }
+  }
+
+  /// Injector for single-process games: `inject.exe`, falling back to `inject_x64.exe`.
+  fn single_inject(&self) -> Option<&'static str> {
+    if self.inject {
+      Some(INJECT)
+    } else if self.inject_x64 {
+      Some(INJECT_X64)
+    } else {
+      None
+    }
+  }
+}
+
+/// Hook DLL and inject target name for the single-process games launched through inject.
+fn single_hook_for(exe_name: &str) -> Option<(&'static str, &'static str)> {
+  match exe_name {
+    "Sinmai.exe" => Some((HOOK_MAI2, "sinmai")),
+    "mu3.exe" => Some((HOOK_MU3, "mu3")),
+    _ => None,
+  }
+}
+
+fn exe_file_name(game: &Game) -> String {
+  Path::new(&game.executable_path)
+    .file_name()
+    .unwrap_or_default()
+    .to_string_lossy()
+    .to_string()
+}
+
+/// Directory the game starts in, matching `store::game_root_dir`: `working_dir` when set,
+/// else the executable's directory. Relative segatools paths resolve against it.
+fn launch_working_dir(game: &Game) -> PathBuf {
+  store::game_root_dir(game)
+    .filter(|dir| !dir.as_os_str().is_empty())
+    .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Applies `game.env`, then `SEGATOOLS_CONFIG_PATH`, which user entries never override.
+fn apply_launch_env(cmd: &mut Command, game: &Game, segatools_ini: &Path) {
+  for (key, value) in &game.env {
+    let key = key.trim();
+    if key.is_empty() || key.eq_ignore_ascii_case("SEGATOOLS_CONFIG_PATH") {
+      continue;
+    }
+    cmd.env(key, value);
+  }
+  cmd.env("SEGATOOLS_CONFIG_PATH", segatools_ini);
+}
 
 fn build_launch_command(game: &Game) -> Result<Command, GameError> {
   if !game.enabled {
     return Err(GameError::Launch("Game is disabled".to_string()));
   }
 
-  let exe_path = Path::new(&game.executable_path);
-  let working_dir = if let Some(dir) = &game.working_dir {
-    Path::new(dir)
-  } else {
-    exe_path.parent().unwrap_or(Path::new("."))
-  };
+  let working_dir = launch_working_dir(game);
 
-  let segatools_root = segatools_root_for_game_id(&game.id);
-  let segatools_ini = segatools_root.join("segatools.ini");
-  let inject_path = segatools_root.join("inject.exe");
-  let inject_x64_path = segatools_root.join("inject_x64.exe");
-  let inject_x86_path = segatools_root.join("inject_x86.exe");
-  let hook_chusan_x64 = segatools_root.join("chusanhook_x64.dll");
-  let hook_chusan_x86 = segatools_root.join("chusanhook_x86.dll");
-  let hook_mai2 = segatools_root.join("mai2hook.dll");
-  let hook_mu3 = segatools_root.join("mu3hook.dll");
-  let has_inject = inject_path.exists() || inject_x86_path.exists() || inject_x64_path.exists();
+  let layout = GameLayout::analyze(&segatools_root_for_game_id(&game.id));
+  let segatools_ini = layout.path("segatools.ini");
+  let has_inject = game.launch_mode != LaunchMode::Direct && layout.has_inject();
 
   // Check if we should use inject (Segatools style)
   if has_inject {
-    let exe_name = exe_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let exe_name = exe_file_name(game);
 
     let mut batch_content = String::new();
     let mut handled = false;
 
     if exe_name == "chusanApp.exe" {
-      let inject_x64 = if inject_x64_path.exists() {
-        Some(&inject_x64_path)
-      } else if inject_path.exists() {
-        Some(&inject_path)
-      } else {
-        None
-      };
-      let inject_x86 = if inject_x86_path.exists() { Some(&inject_x86_path) } else { None };
+      let inject_x64 = layout.chusan_inject_x64().map(|name| layout.path(name));
+      let inject_x86 = if layout.inject_x86 { Some(layout.path(INJECT_X86)) } else { None };
 
       if let (Some(inject_x64), Some(inject_x86)) = (inject_x64, inject_x86) {
         batch_content.push_str("@echo off\r\n");
@@ -54,69 +216,47 @@ fn build_launch_command(game: &Game) -> Result<Command, GameError> {
         batch_content.push_str(&format!(
           "start \"\" /min \"{}\" -d -k \"{}\" amdaemon.exe -c config_common.json config_server.json config_client.json config_cvt.json config_sp.json config_hook.json\r\n",
           inject_x64.to_string_lossy(),
-          hook_chusan_x64.to_string_lossy()
+          layout.path(HOOK_CHUSAN_X64).to_string_lossy()
         ));
 
-        let args_str = game.launch_args.join(" ");
-        batch_content.push_str(&format!(
-          "\"{}\" -d -k \"{}\" chusanApp.exe {}\r\n",
-          inject_x86.to_string_lossy(),
-          hook_chusan_x86.to_string_lossy(),
-          args_str
+        batch_content.push_str(&inject_line(
+          &inject_x86,
+          &layout.path(HOOK_CHUSAN_X86),
+          "chusanApp.exe",
+          &game.launch_args,
         ));
         batch_content.push_str("taskkill /f /im amdaemon.exe > nul 2>&1\r\n");
         handled = true;
       }
-    } else {
-      let (hook_dll, target_name) = match exe_name.as_str() {
-        "Sinmai.exe" => (Some(&hook_mai2), "sinmai"),
-        "mu3.exe" => (Some(&hook_mu3), "mu3"),
-        _ => (None, "")
-      };
-
-      let inject = if inject_path.exists() {
-        Some(&inject_path)
-      } else if inject_x64_path.exists() {
-        Some(&inject_x64_path)
-      } else {
-        None
-      };
-
-      if hook_dll.is_some() && inject.is_some() {
-        let amdaemon_path = working_dir.join("amdaemon.exe");
-        let has_amdaemon = amdaemon_path.exists();
-        let inject = inject.unwrap();
-        let hook_dll = hook_dll.unwrap();
+    } else if let (Some((hook_name, target_name)), Some(inject_name)) =
+      (single_hook_for(&exe_name), layout.single_inject())
+    {
+      let amdaemon_path = working_dir.join("amdaemon.exe");
+      let has_amdaemon = amdaemon_path.exists();
+      let inject = layout.path(inject_name);
+      let hook_dll = layout.path(hook_name);
 
-        batch_content.push_str("@echo off\r\n");
-        batch_content.push_str(&format!("cd /d \"{}\"\r\n", working_dir.to_string_lossy()));
+      batch_content.push_str("@echo off\r\n");
+      batch_content.push_str(&format!("cd /d \"{}\"\r\n", working_dir.to_string_lossy()));
 
-        if has_amdaemon {
-          batch_content.push_str(&format!(
-            "start \"\" /min \"{}\" -d -k \"{}\" amdaemon.exe -f -c config_common.json config_server.json config_client.json\r\n",
-            inject.to_string_lossy(),
-            hook_dll.to_string_lossy()
-          ));
-        }
-
-        let args_str = game.launch_args.join(" ");
+      if has_amdaemon {
         batch_content.push_str(&format!(
-          "\"{}\" -d -k \"{}\" {} {}\r\n",
+          "start \"\" /min \"{}\" -d -k \"{}\" amdaemon.exe -f -c config_common.json config_server.json config_client.json\r\n",
           inject.to_string_lossy(),
-          hook_dll.to_string_lossy(),
-          target_name,
-          args_str
+          hook_dll.to_string_lossy()
         ));
+      }
 
-        if has_amdaemon {
-          batch_content.push_str("taskkill /f /im amdaemon.exe > nul 2>&1\r\n");
-        }
-        handled = true;
+      batch_content.push_str(&inject_line(&inject, &hook_dll, target_name, &game.launch_args));
+
+      if has_amdaemon {
+        batch_content.push_str("taskkill /f /im amdaemon.exe > nul 2>&1\r\n");
       }
+      handled = true;
     }
 
     if handled {
-      let batch_path = segatools_root.join("launch_temp.bat");
+      let batch_path = layout.path("launch_temp.bat");
       if let Some(parent) = batch_path.parent() {
         fs::create_dir_all(parent)
           .map_err(|e| GameError::Launch(format!("Failed to create segatools dir: {}", e)))?;
@@ -127,7 +267,7 @@ fn build_launch_command(game: &Game) -> Result<Command, GameError> {
       let mut cmd = Command::new("cmd");
       cmd.args(&["/c", batch_path.to_str().unwrap()]);
       cmd.current_dir(working_dir);
-      cmd.env("SEGATOOLS_CONFIG_PATH", &segatools_ini);
+      apply_launch_env(&mut cmd, game, &segatools_ini);
       cmd.creation_flags(CREATE_NEW_CONSOLE);
       return Ok(cmd);
     }
@@ -135,17 +275,214 @@ fn build_launch_command(game: &Game) -> Result<Command, GameError> {
 
   // Fallback to normal launch
   let mut cmd = Command::new(&game.executable_path);
-  if let Some(dir) = &game.working_dir {
-    if !dir.is_empty() {
-      cmd.current_dir(dir);
-    }
-  }
+  cmd.current_dir(&working_dir);
   cmd.args(&game.launch_args);
-  cmd.env("SEGATOOLS_CONFIG_PATH", &segatools_ini);
+  apply_launch_env(&mut cmd, game, &segatools_ini);
   cmd.creation_flags(CREATE_NEW_CONSOLE);
   Ok(cmd)
 }
 
+/// Segatools files a launch of `game` relies on but that are missing, by file name.
+pub struct MissingLaunchFiles {
+  pub inject: Vec<String>,
+  pub hook: Vec<String>,
+}
+
+/// Checks the files `build_launch_command` would inject with. `None` when the game
+/// launches directly: `LaunchMode::Direct` or an executable without a known hook.
+pub fn missing_launch_files(game: &Game) -> Option<MissingLaunchFiles> {
+  if game.launch_mode == LaunchMode::Direct {
+    return None;
+  }
+  let exe_name = exe_file_name(game);
+  let layout = GameLayout::analyze(&segatools_root_for_game_id(&game.id));
+  let mut missing = MissingLaunchFiles {
+    inject: Vec::new(),
+    hook: Vec::new(),
+  };
+
+  if exe_name == "chusanApp.exe" {
+    if layout.chusan_inject_x64().is_none() {
+      missing.inject.push(format!("{INJECT_X64} / {INJECT}"));
+    }
+    if !layout.inject_x86 {
+      missing.inject.push(INJECT_X86.to_string());
+    }
+    if !layout.hook_chusan_x64 {
+      missing.hook.push(HOOK_CHUSAN_X64.to_string());
+    }
+    if !layout.hook_chusan_x86 {
+      missing.hook.push(HOOK_CHUSAN_X86.to_string());
+    }
+  } else {
+    let (hook_name, _) = single_hook_for(&exe_name)?;
+    if layout.single_inject().is_none() {
+      missing.inject.push(format!("{INJECT} / {INJECT_X64}"));
+    }
+    let hook_present = match hook_name {
+      HOOK_MAI2 => layout.hook_mai2,
+      _ => layout.hook_mu3,
+    };
+    if !hook_present {
+      missing.hook.push(hook_name.to_string());
+    }
+  }
+  Some(missing)
+}
+
+/// Resolves a path from segatools.ini the way the game sees it: relative to
+/// `launch_working_dir`, absolute paths as-is.
+pub fn resolve_game_path(game: &Game, raw: &str) -> PathBuf {
+  launch_working_dir(game).join(raw.trim())
+}
+
+/// A file segatools.ini points the game at, such as `DEVICE\aime.txt`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceFile {
+  /// The ini setting, e.g. `aime.aimePath`.
+  pub key: String,
+  pub path: String,
+  pub exists: bool,
+  pub size: Option<u64>,
+}
+
+/// Reports presence and size of every device file `cfg` references, in a fixed order.
+/// Empty paths are left out.
+pub fn device_files(game: &Game, cfg: &SegatoolsConfig) -> Vec<DeviceFile> {
+  let entries = [
+    ("aime.aimePath", &cfg.aime.aime_path),
+    ("aime.felicaPath", &cfg.aime.felica_path),
+    ("aime.authdataPath", &cfg.aime.authdata_path),
+    ("eeprom.path", &cfg.eeprom.path),
+    ("sram.path", &cfg.sram.path),
+    ("keychip.billingCa", &cfg.keychip.billing_ca),
+    ("keychip.billingPub", &cfg.keychip.billing_pub),
+  ];
+  entries
+    .into_iter()
+    .filter(|(_, raw)| !raw.trim().is_empty())
+    .map(|(key, raw)| {
+      let path = resolve_game_path(game, raw);
+      let size = fs::metadata(&path).ok().filter(|m| m.is_file()).map(|m| m.len());
+      DeviceFile {
+        key: key.to_string(),
+        path: path.to_string_lossy().to_string(),
+        exists: size.is_some(),
+        size,
+      }
+    })
+    .collect()
+}
+
+/// Creates the directories of the enabled `[eeprom]`/`[sram]` paths (`DEVICE` by default)
+/// when missing and checks they are writable. Games fail cryptically at boot without them.
+/// Returns one message per directory that is not usable.
+pub fn ensure_device_dirs(game: &Game, cfg: &SegatoolsConfig) -> Vec<String> {
+  let working_dir = launch_working_dir(game);
+  let mut dirs: Vec<PathBuf> = Vec::new();
+  for (enabled, path) in [(cfg.eeprom.enable, &cfg.eeprom.path), (cfg.sram.enable, &cfg.sram.path)] {
+    let path = path.trim();
+    if !enabled || path.is_empty() {
+      continue;
+    }
+    let dir = working_dir.join(path).parent().map(Path::to_path_buf).unwrap_or_else(|| working_dir.clone());
+    if !dirs.contains(&dir) {
+      dirs.push(dir);
+    }
+  }
+
+  let mut problems = Vec::new();
+  for dir in dirs {
+    if let Err(e) = fs::create_dir_all(&dir) {
+      problems.push(format!("Failed to create {}: {}", dir.display(), e));
+      continue;
+    }
+    let probe = dir.join(".iris_write_test");
+    match fs::write(&probe, b"") {
+      Ok(()) => {
+        let _ = fs::remove_file(&probe);
+      }
+      Err(e) => problems.push(format!("{} is not writable: {}", dir.display(), e)),
+    }
+  }
+  problems
+}
+
+/// Starts `game.pre_launch`, if set, and waits at most `PRE_LAUNCH_HOOK_WAIT` for it. A hook
+/// still running after that is left to finish on a background thread. Failures are logged
+/// to `HOOK_LOG` and never stop the launch.
+pub fn run_pre_launch_hook(game: &Game) {
+  let Some(command) = hook_command_line(&game.pre_launch) else {
+    return;
+  };
+  let child = hook_command(game, command)
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn();
+  let child = match child {
+    Ok(child) => child,
+    Err(e) => {
+      log_hook_result(&game.id, "pre_launch", command, Err(e));
+      return;
+    }
+  };
+  let (done_tx, done_rx) = mpsc::channel();
+  let game_id = game.id.clone();
+  let command = command.to_string();
+  thread::spawn(move || {
+    let result = child.wait_with_output();
+    let _ = done_tx.send(());
+    log_hook_result(&game_id, "pre_launch", &command, result);
+  });
+  let _ = done_rx.recv_timeout(PRE_LAUNCH_HOOK_WAIT);
+}
+
+/// Runs `game.post_exit`, if set, and waits for it. Failures are logged to `HOOK_LOG`.
+pub fn run_post_exit_hook(game: &Game) {
+  if let Some(command) = hook_command_line(&game.post_exit) {
+    let result = hook_command(game, command).output();
+    log_hook_result(&game.id, "post_exit", command, result);
+  }
+}
+
+fn hook_command_line(command: &Option<String>) -> Option<&str> {
+  command.as_deref().map(str::trim).filter(|command| !command.is_empty())
+}
+
+fn hook_command(game: &Game, command: &str) -> Command {
+  let mut cmd = Command::new("cmd");
+  cmd.arg("/c").raw_arg(command);
+  cmd.current_dir(launch_working_dir(game));
+  cmd.creation_flags(CREATE_NO_WINDOW);
+  cmd
+}
+
+/// Appends a failed hook run to `HOOK_LOG`; a successful one is not logged.
+fn log_hook_result(game_id: &str, label: &str, command: &str, result: io::Result<Output>) {
+  let failure = match result {
+    Ok(output) if output.status.success() => return,
+    Ok(output) => format!(
+      "exited with {}\n{}{}",
+      output.status,
+      String::from_utf8_lossy(&output.stdout),
+      String::from_utf8_lossy(&output.stderr)
+    ),
+    Err(e) => format!("failed to start: {}", e),
+  };
+  let log_path = segatools_root_for_game_id(game_id).join(HOOK_LOG);
+  let entry = format!(
+    "[{}] {} `{}` {}\n",
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+    label,
+    command,
+    failure.trim_end()
+  );
+  let _ = fs::create_dir_all(segatools_root_for_game_id(game_id));
+  if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(log_path) {
+    let _ = file.write_all(entry.as_bytes());
+  }
+}
+
 pub fn launch_game(game: &Game) -> Result<(), GameError> {
   let mut cmd = build_launch_command(game)?;
   cmd.spawn().map_err(|e| GameError::Launch(e.to_string()))?;
@@ -156,3 +493,216 @@ pub fn launch_game_child(game: &Game) -> Result<Child, GameError> {
   let mut cmd = build_launch_command(game)?;
   cmd.spawn().map_err(|e| GameError::Launch(e.to_string()))
 }
+
+/// Runs the launch command without a console window and waits for it to exit,
+/// returning the combined stdout/stderr. A non-zero exit is reported as an error
+/// carrying the same output so inject failures can be diagnosed.
+pub fn launch_game_debug(game: &Game) -> Result<String, GameError> {
+  let mut cmd = build_launch_command(game)?;
+  cmd.creation_flags(CREATE_NO_WINDOW);
+  let output = cmd.output().map_err(|e| GameError::Launch(e.to_string()))?;
+
+  let mut combined = String::from_utf8_lossy(&output.stdout).to_string();
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  if !stderr.trim().is_empty() {
+    if !combined.is_empty() && !combined.ends_with('\n') {
+      combined.push('\n');
+    }
+    combined.push_str(&stderr);
+  }
+
+  if output.status.success() {
+    return Ok(combined);
+  }
+  let code = output
+    .status
+    .code()
+    .map(|code| code.to_string())
+    .unwrap_or_else(|| "unknown".to_string());
+  Err(GameError::Launch(format!("Process exited with code {}:\n{}", code, combined.trim_end())))
+}
+
+const GAME_EXIT_POLL: Duration = Duration::from_secs(2);
+
+/// Whether a process named after the game's executable is running.
+pub fn game_running(game: &Game) -> bool {
+  let exe_name = exe_file_name(game);
+  if exe_name.is_empty() {
+    return false;
+  }
+  let output = Command::new("tasklist")
+    .args(["/nh", "/fo", "csv", "/fi", &format!("IMAGENAME eq {}", exe_name)])
+    .creation_flags(CREATE_NO_WINDOW)
+    .output();
+  match output {
+    Ok(output) => String::from_utf8_lossy(&output.stdout)
+      .to_ascii_lowercase()
+      .contains(&format!("\"{}\"", exe_name.to_ascii_lowercase())),
+    Err(_) => false,
+  }
+}
+
+/// Blocks until the game started through `child` has exited.
+///
+/// `child` is whatever `launch_game_child` spawned: the `cmd /c` running the launch
+/// batch, or the game executable itself. The batch runs the inject of the game in the
+/// foreground, but a game executable may also hand off to another process and return
+/// early, so once `child` exits this keeps polling for the game's image name.
+pub fn wait_for_game_exit(game: &Game, child: &mut Child) {
+  let _ = child.wait();
+  while game_running(game) {
+    thread::sleep(GAME_EXIT_POLL);
+  }
+}
+
+fn taskkill(args: &[&str]) -> Result<(), GameError> {
+  // taskkill exits non-zero when nothing matched, which is fine here.
+  Command::new("taskkill")
+    .args(args)
+    .creation_flags(CREATE_NO_WINDOW)
+    .output()
+    .map_err(|e| GameError::Launch(format!("Failed to run taskkill: {}", e)))?;
+  Ok(())
+}
+
+/// Force-stops a launched game: the process tree under `pid` (the launch batch and
+/// everything it started), then any leftover game executable and `amdaemon.exe`.
+pub fn stop_game(game: &Game, pid: Option<u32>) -> Result<(), GameError> {
+  if let Some(pid) = pid {
+    taskkill(&["/f", "/t", "/pid", &pid.to_string()])?;
+  }
+  let exe_name = exe_file_name(game);
+  if !exe_name.is_empty() {
+    taskkill(&["/f", "/im", &exe_name])?;
+  }
+  taskkill(&["/f", "/im", "amdaemon.exe"])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn quote_batch_arg_leaves_plain_args() {
+    assert_eq!(quote_batch_arg("-k"), "-k");
+    assert_eq!(quote_batch_arg(""), "\"\"");
+  }
+
+  #[test]
+  fn quote_batch_arg_quotes_spaces() {
+    assert_eq!(quote_batch_arg("a b"), "\"a b\"");
+    assert_eq!(quote_batch_arg("C:\\dir with space\\"), "\"C:\\dir with space\\\\\"");
+  }
+
+  #[test]
+  fn quote_batch_arg_doubles_percent() {
+    assert_eq!(quote_batch_arg("50%"), "50%%");
+    assert_eq!(quote_batch_arg("%PATH% x"), "\"%%PATH%% x\"");
+  }
+
+  #[test]
+  fn quote_batch_arg_keeps_caret_inside_quotes() {
+    assert_eq!(quote_batch_arg("a^b"), "\"a^b\"");
+  }
+
+  #[test]
+  fn quote_batch_arg_escapes_embedded_quotes() {
+    assert_eq!(quote_batch_arg("a\"b"), "\"a\\\"b\"");
+    // The escaped quote ends cmd.exe's quoted run, so the `&` after it needs a caret.
+    assert_eq!(quote_batch_arg("a\"b&c"), "\"a\\\"b^&c\"");
+  }
+
+  #[test]
+  fn inject_line_quotes_launch_args() {
+    let args: Vec<String> = ["-path", "C:\\Games\\My Game", "50%", "a&b%c"]
+      .iter()
+      .map(|arg| arg.to_string())
+      .collect();
+    let line = inject_line(
+      Path::new("C:\\seg\\inject.exe"),
+      Path::new("C:\\seg\\mai2hook.dll"),
+      "sinmai",
+      &args,
+    );
+    assert_eq!(
+      line,
+      "\"C:\\seg\\inject.exe\" -d -k \"C:\\seg\\mai2hook.dll\" sinmai -path \"C:\\Games\\My Game\" 50%% \"a&b%%c\"\r\n"
+    );
+  }
+
+  #[test]
+  fn apply_launch_env_sets_user_env_but_not_config_path() {
+    let game: Game = serde_json::from_value(serde_json::json!({
+      "id": "1",
+      "name": "game",
+      "executable_path": "C:\\game\\app.exe",
+      "launch_args": [],
+      "enabled": true,
+      "tags": [],
+      "env": { "FOO": "bar", " ": "blank", "segatools_config_path": "C:\\other.ini" },
+    }))
+    .unwrap();
+    let mut cmd = Command::new("app.exe");
+    apply_launch_env(&mut cmd, &game, Path::new("C:\\seg\\segatools.ini"));
+
+    let mut envs: Vec<(String, String)> = cmd
+      .get_envs()
+      .map(|(key, value)| {
+        let value = value.unwrap_or_default();
+        (key.to_string_lossy().into_owned(), value.to_string_lossy().into_owned())
+      })
+      .collect();
+    envs.sort();
+    assert_eq!(
+      envs,
+      vec![
+        ("FOO".to_string(), "bar".to_string()),
+        ("SEGATOOLS_CONFIG_PATH".to_string(), "C:\\seg\\segatools.ini".to_string()),
+      ]
+    );
+  }
+
+  fn layout_with(files: &[&str]) -> (tempfile::TempDir, GameLayout) {
+    let dir = tempfile::tempdir().unwrap();
+    for file in files {
+      fs::write(dir.path().join(file), b"").unwrap();
+    }
+    let layout = GameLayout::analyze(dir.path());
+    (dir, layout)
+  }
+
+  #[test]
+  fn layout_without_files_has_no_injector() {
+    let (_dir, layout) = layout_with(&[]);
+    assert!(!layout.has_inject());
+    assert_eq!(layout.chusan_inject_x64(), None);
+    assert_eq!(layout.single_inject(), None);
+    assert!(!layout.hook_mai2 && !layout.hook_mu3 && !layout.hook_chusan_x64 && !layout.hook_chusan_x86);
+  }
+
+  #[test]
+  fn layout_prefers_matching_injector() {
+    let (_dir, layout) = layout_with(&[INJECT, INJECT_X64]);
+    assert_eq!(layout.chusan_inject_x64(), Some(INJECT_X64));
+    assert_eq!(layout.single_inject(), Some(INJECT));
+  }
+
+  #[test]
+  fn layout_falls_back_to_other_injector() {
+    let (_dir, layout) = layout_with(&[INJECT, HOOK_CHUSAN_X86]);
+    assert_eq!(layout.chusan_inject_x64(), Some(INJECT));
+    assert!(layout.hook_chusan_x86 && !layout.hook_chusan_x64);
+
+    let (_dir, layout) = layout_with(&[INJECT_X64, HOOK_MU3]);
+    assert_eq!(layout.single_inject(), Some(INJECT_X64));
+    assert!(layout.hook_mu3 && !layout.hook_mai2);
+  }
+
+  #[test]
+  fn layout_x86_injector_alone_counts_as_inject() {
+    let (dir, layout) = layout_with(&[INJECT_X86]);
+    assert!(layout.has_inject());
+    assert_eq!(layout.single_inject(), None);
+    assert_eq!(layout.path("segatools.ini"), dir.path().join("segatools.ini"));
+  }
+}
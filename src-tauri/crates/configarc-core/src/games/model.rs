@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum LaunchMode {
   Folder,
   Vhd,
+  /// Runs `executable_path` as-is, even when segatools inject binaries are deployed.
+  Direct,
 }
 
 impl Default for LaunchMode {
@@ -24,4 +27,18 @@ pub struct Game {
   pub tags: Vec<String>,
   #[serde(default)]
   pub launch_mode: LaunchMode,
+  /// Position chosen by the user; unordered games sort after ordered ones by name.
+  #[serde(default)]
+  pub order: Option<u32>,
+  /// Extra environment variables for the launched process. `SEGATOOLS_CONFIG_PATH` is
+  /// always set by the launcher and cannot be overridden here.
+  #[serde(default)]
+  pub env: HashMap<String, String>,
+  /// Command run through `cmd /c` before the game starts, after any VHD is mounted.
+  /// The launch waits up to a few seconds for it; a long-running helper keeps running.
+  #[serde(default)]
+  pub pre_launch: Option<String>,
+  /// Command run through `cmd /c` after the game exits, before any VHD is unmounted.
+  #[serde(default)]
+  pub post_exit: Option<String>,
 }
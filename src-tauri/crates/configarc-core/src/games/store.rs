@@ -1,10 +1,13 @@
 use super::model::Game;
+use crate::config::bundle::SEGATOOLS_FILES;
+use crate::config::paths::{app_data_file, segatools_root_for_game_id, write_atomic};
 use crate::error::GameError;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 fn games_path() -> PathBuf {
-  Path::new(".").join("configarc_games.json")
+  app_data_file("configarc_games.json")
 }
 
 pub fn list_games() -> Result<Vec<Game>, GameError> {
@@ -16,20 +19,83 @@ pub fn list_games() -> Result<Vec<Game>, GameError> {
   if data.trim().is_empty() {
     return Ok(vec![]);
   }
-  let games: Vec<Game> = serde_json::from_str(&data)?;
+  let mut games: Vec<Game> = serde_json::from_str(&data)?;
+  games.sort_by(|a, b| {
+    let a_key = (a.order.is_none(), a.order, a.name.to_lowercase());
+    let b_key = (b.order.is_none(), b.order, b.name.to_lowercase());
+    a_key.cmp(&b_key)
+  });
   Ok(games)
 }
 
+/// Games carrying `tag`, compared case-insensitively, in `list_games` order.
+pub fn list_games_with_tag(tag: &str) -> Result<Vec<Game>, GameError> {
+  let tag = tag.trim().to_lowercase();
+  Ok(
+    list_games()?
+      .into_iter()
+      .filter(|g| g.tags.iter().any(|t| t.trim().to_lowercase() == tag))
+      .collect(),
+  )
+}
+
+/// Every tag used by a saved game, deduplicated case-insensitively and sorted. The first
+/// spelling seen is kept.
+pub fn all_tags() -> Result<Vec<String>, GameError> {
+  let mut tags = BTreeMap::new();
+  for game in list_games()? {
+    for tag in game.tags {
+      let tag = tag.trim();
+      if !tag.is_empty() {
+        tags.entry(tag.to_lowercase()).or_insert_with(|| tag.to_string());
+      }
+    }
+  }
+  Ok(tags.into_values().collect())
+}
+
+fn write_games(games: &[Game]) -> Result<(), GameError> {
+  let json = serde_json::to_string_pretty(games)?;
+  write_atomic(&games_path(), json)?;
+  Ok(())
+}
+
 pub fn save_game(game: Game) -> Result<(), GameError> {
   let mut games = list_games()?;
   games.retain(|g| g.id != game.id);
   games.push(game.clone());
+  write_games(&games)
+}
 
-  let path = games_path();
-  let json = serde_json::to_string_pretty(&games)?;
-  fs::write(path, json)?;
+/// Checks that the executable exists and that `working_dir`, when set, is a directory.
+/// These are reported rather than enforced by `save_game`, since a game may live on a
+/// drive or VHD that is not mounted yet.
+pub fn check_game_paths(game: &Game) -> Vec<GameError> {
+  let mut problems = Vec::new();
+  if !Path::new(&game.executable_path).is_file() {
+    problems.push(GameError::MissingExecutable(game.executable_path.clone()));
+  }
+  if let Some(dir) = game.working_dir.as_deref().filter(|d| !d.is_empty()) {
+    if !Path::new(dir).is_dir() {
+      problems.push(GameError::InvalidWorkingDir(dir.to_string()));
+    }
+  }
+  problems
+}
 
-  Ok(())
+fn normalize_exe_path(path: &str) -> String {
+  path.trim().replace('/', "\\").to_lowercase()
+}
+
+/// Returns the id of another saved game that points at the same executable.
+pub fn find_duplicate_game(game: &Game) -> Result<Option<String>, GameError> {
+  let exe = normalize_exe_path(&game.executable_path);
+  Ok(
+    list_games()?
+      .into_iter()
+      .find(|g| g.id != game.id && normalize_exe_path(&g.executable_path) == exe)
+      .map(|g| g.id),
+  )
 }
 
 pub fn delete_game(id: &str) -> Result<(), GameError> {
@@ -39,12 +105,106 @@ pub fn delete_game(id: &str) -> Result<(), GameError> {
   if games.len() == before {
     return Err(GameError::NotFound(id.to_string()));
   }
-  let path = games_path();
-  let json = serde_json::to_string_pretty(&games)?;
-  fs::write(path, json)?;
+  write_games(&games)
+}
+
+/// Copies the config files a bundle carries (segatools.ini, vhd.json, saved profiles) from
+/// one segatools root to another. Runtime files such as VHD copies, trusted backups and
+/// hashes stay behind.
+fn copy_segatools_config(from: &Path, to: &Path) -> Result<(), GameError> {
+  for file in SEGATOOLS_FILES {
+    let src = from.join(file);
+    if !src.is_file() {
+      continue;
+    }
+    let dest = to.join(file);
+    if let Some(parent) = dest.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    fs::copy(&src, &dest)?;
+  }
+  Ok(())
+}
+
+/// Saves a copy of game `id` named `new_name` under a fresh id, along with copies of its
+/// segatools config files. The `config_*.json` files live in the game folder, which the
+/// copy shares. Nothing is left behind if the copy fails.
+pub fn duplicate_game(id: &str, new_name: &str) -> Result<Game, GameError> {
+  let source = list_games()?
+    .into_iter()
+    .find(|g| g.id == id)
+    .ok_or_else(|| GameError::NotFound(id.to_string()))?;
+
+  let mut game = source.clone();
+  game.id = chrono::Utc::now().timestamp_millis().to_string();
+  game.name = new_name.trim().to_string();
+  game.order = None;
+
+  let to = segatools_root_for_game_id(&game.id);
+  let result = copy_segatools_config(&segatools_root_for_game_id(&source.id), &to)
+    .and_then(|_| save_game(game.clone()));
+  if let Err(err) = result {
+    let _ = fs::remove_dir_all(&to);
+    return Err(err);
+  }
+  Ok(game)
+}
+
+/// Changes game `old_id` to `new_id` and moves `Segatools/<old_id>` along with it, so the
+/// config is not orphaned. The directory is moved with a single rename before the game list
+/// is rewritten, and moved back if that write fails. Rejects an id that another game or
+/// segatools directory already uses. The caller updates the active game.
+pub fn rename_game_id(old_id: &str, new_id: &str) -> Result<(), GameError> {
+  let new_id = new_id.trim();
+  if new_id.is_empty() || new_id.contains(['/', '\\', ':']) || new_id == "." || new_id == ".." {
+    return Err(GameError::InvalidId(new_id.to_string()));
+  }
+  let mut games = list_games()?;
+  if !games.iter().any(|g| g.id == old_id) {
+    return Err(GameError::NotFound(old_id.to_string()));
+  }
+  if old_id == new_id {
+    return Ok(());
+  }
+  let to = segatools_root_for_game_id(new_id);
+  if games.iter().any(|g| g.id == new_id) || to.exists() {
+    return Err(GameError::InvalidId(format!("{new_id} is already in use")));
+  }
+
+  let from = segatools_root_for_game_id(old_id);
+  let moved = from.exists();
+  if moved {
+    fs::rename(&from, &to)?;
+  }
+  for game in games.iter_mut().filter(|g| g.id == old_id) {
+    game.id = new_id.to_string();
+  }
+  if let Err(err) = write_games(&games) {
+    if moved {
+      let _ = fs::rename(&to, &from);
+    }
+    return Err(err);
+  }
   Ok(())
 }
 
+/// Assigns sequential `order` values following `ids`; games missing from `ids` keep
+/// their current relative order after the listed ones.
+pub fn reorder_games(ids: &[String]) -> Result<(), GameError> {
+  let mut games = list_games()?;
+  for id in ids {
+    if !games.iter().any(|g| &g.id == id) {
+      return Err(GameError::NotFound(id.to_string()));
+    }
+  }
+  let rank = |game: &Game| ids.iter().position(|id| id == &game.id).unwrap_or(ids.len());
+  games.sort_by_key(|game| rank(game));
+  for (index, game) in games.iter_mut().enumerate() {
+    game.order = Some(index as u32);
+  }
+  write_games(&games)
+}
+
 pub fn game_root_dir(game: &Game) -> Option<PathBuf> {
   if let Some(dir) = &game.working_dir {
     if !dir.is_empty() {
@@ -53,3 +213,49 @@ pub fn game_root_dir(game: &Game) -> Option<PathBuf> {
   }
   Path::new(&game.executable_path).parent().map(|p| p.to_path_buf())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn game_at(executable_path: &Path, working_dir: Option<&Path>) -> Game {
+    serde_json::from_value(serde_json::json!({
+      "id": "1",
+      "name": "game",
+      "executable_path": executable_path.to_string_lossy(),
+      "working_dir": working_dir.map(|dir| dir.to_string_lossy()),
+      "launch_args": [],
+      "enabled": true,
+      "tags": [],
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn check_game_paths_accepts_existing_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let exe = dir.path().join("app.exe");
+    fs::write(&exe, b"").unwrap();
+    assert!(check_game_paths(&game_at(&exe, Some(dir.path()))).is_empty());
+    assert!(check_game_paths(&game_at(&exe, None)).is_empty());
+  }
+
+  #[test]
+  fn check_game_paths_reports_missing_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let exe = dir.path().join("app.exe");
+    let problems = check_game_paths(&game_at(&exe, Some(&dir.path().join("missing"))));
+    assert_eq!(problems.len(), 2);
+    assert!(matches!(problems[0], GameError::MissingExecutable(_)));
+    assert!(matches!(problems[1], GameError::InvalidWorkingDir(_)));
+  }
+
+  #[test]
+  fn check_game_paths_rejects_file_as_working_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let exe = dir.path().join("app.exe");
+    fs::write(&exe, b"").unwrap();
+    let problems = check_game_paths(&game_at(&exe, Some(&exe)));
+    assert!(matches!(problems.as_slice(), [GameError::InvalidWorkingDir(_)]));
+  }
+}
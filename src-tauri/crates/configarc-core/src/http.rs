@@ -0,0 +1,78 @@
+//! Builds the blocking HTTP clients used for remote config, update checks, key files and
+//! trusted supply-chain downloads, so they share timeouts, user agent and proxy settings.
+
+use reqwest::blocking::{Client, ClientBuilder};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub const DEFAULT_USER_AGENT: &str = "ConfigArcLauncher";
+
+static USE_SYSTEM_PROXY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Limit for the whole request, including reading the body.
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub user_agent: String,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+}
+
+impl HttpOptions {
+    /// Short limits for requests on the startup path, which should fail fast.
+    pub fn quick() -> Self {
+        Self {
+            timeout: Duration::from_secs(6),
+            connect_timeout: Duration::from_secs(4),
+            ..Self::default()
+        }
+    }
+
+    /// Longer limits for artifact downloads that can be hundreds of megabytes.
+    pub fn download() -> Self {
+        Self {
+            timeout: Duration::from_secs(60),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+}
+
+/// Routes requests through the system proxy (`network.use_system_proxy`). Off by
+/// default, so clients connect directly unless the setting is turned on.
+pub fn set_use_system_proxy(enabled: bool) {
+    USE_SYSTEM_PROXY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn use_system_proxy() -> bool {
+    USE_SYSTEM_PROXY.load(Ordering::Relaxed)
+}
+
+fn apply_proxy(builder: ClientBuilder) -> ClientBuilder {
+    if use_system_proxy() {
+        builder
+    } else {
+        builder.no_proxy()
+    }
+}
+
+pub fn build_client(options: HttpOptions) -> reqwest::Result<Client> {
+    apply_proxy(Client::builder())
+        .timeout(options.timeout)
+        .connect_timeout(options.connect_timeout)
+        .user_agent(options.user_agent)
+        .build()
+}
@@ -1,5 +1,8 @@
 pub mod config;
 pub mod error;
 pub mod games;
+pub mod http;
 pub mod trusted;
 pub mod vhd;
+#[cfg(all(target_os = "windows", feature = "virtdisk"))]
+mod virtdisk;
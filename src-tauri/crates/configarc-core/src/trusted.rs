@@ -1,15 +1,16 @@
-use crate::config::paths::{get_active_game_id, segatools_root_for_active};
+use crate::config::paths::{app_root_dir, get_active_game_id, segatools_root_for_active};
 use crate::games::{model::Game, store};
+use crate::http::{build_client, HttpOptions};
 use chrono::Utc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use minisign_verify::{PublicKey, Signature};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use sha2::{Digest, Sha256};
 use std::fs;
-use std::io::{Read, Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, SystemTime};
 use tempfile::NamedTempFile;
@@ -23,10 +24,10 @@ const PUBLIC_KEY: &str = "untrusted comment: minisign public key 56F1F4A46FE3CC0
 const BACKUP_DIR: &str = "Segatools_Backup";
 const BACKUP_FILES_DIR: &str = "files";
 const BACKUP_META_NAME: &str = "metadata.json";
+const DEFAULT_BACKUP_GENERATIONS: usize = 3;
 const TRUST_CACHE_TTL_SECS: u64 = 300;
-const TRUST_TIMEOUT_SECS: u64 = 60;
-const TRUST_CONNECT_TIMEOUT_SECS: u64 = 10;
 const TRUST_CACHE_FILE_NAME: &str = ".trust_cache.json";
+const MANIFEST_CACHE_NAME: &str = ".trusted_manifest.json";
 
 #[derive(Debug, Error)]
 pub enum TrustedError {
@@ -132,6 +133,12 @@ pub struct FileCheckResult {
     pub actual_sha256: Option<String>,
     pub exists: bool,
     pub matches: bool,
+    /// PE link time of the local binary.
+    #[serde(default)]
+    pub build_time: Option<String>,
+    /// Target architecture from the PE header, e.g. `x86` or `x64`.
+    #[serde(default)]
+    pub architecture: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -151,7 +158,22 @@ pub struct SegatoolsTrustStatus {
     pub local_build_time: Option<String>,
 }
 
-fn get_pe_timestamp(path: &Path) -> Option<u32> {
+struct PeHeader {
+    machine: u16,
+    timestamp: u32,
+}
+
+fn pe_architecture(machine: u16) -> String {
+    match machine {
+        0x014c => "x86".to_string(),
+        0x8664 => "x64".to_string(),
+        0xaa64 => "arm64".to_string(),
+        0x01c4 => "arm".to_string(),
+        other => format!("unknown (0x{:04x})", other),
+    }
+}
+
+fn read_pe_header(path: &Path) -> Option<PeHeader> {
     let mut file = fs::File::open(path).ok()?;
     let mut dos_header = [0u8; 0x40];
     file.read_exact(&mut dos_header).ok()?;
@@ -169,13 +191,14 @@ fn get_pe_timestamp(path: &Path) -> Option<u32> {
         return None;
     }
     
-    // Skip Machine (2) + NumberOfSections (2)
-    file.seek(SeekFrom::Current(4)).ok()?;
+    // Machine (2) + NumberOfSections (2) + TimeDateStamp (4)
+    let mut header = [0u8; 8];
+    file.read_exact(&mut header).ok()?;
     
-    let mut timestamp_bytes = [0u8; 4];
-    file.read_exact(&mut timestamp_bytes).ok()?;
-    
-    Some(u32::from_le_bytes(timestamp_bytes))
+    Some(PeHeader {
+        machine: u16::from_le_bytes([header[0], header[1]]),
+        timestamp: u32::from_le_bytes(header[4..8].try_into().ok()?),
+    })
 }
 
 fn format_timestamp(ts: u32) -> String {
@@ -190,6 +213,9 @@ fn format_timestamp(ts: u32) -> String {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupMetadata {
+    /// Name of the backup directory under `Segatools_Backup`.
+    #[serde(default)]
+    pub id: String,
     pub created_at: String,
     pub artifact_name: String,
     pub artifact_sha256: String,
@@ -206,8 +232,20 @@ pub struct DeployResult {
     pub backup_dir: Option<String>,
     pub message: Option<String>,
     pub verification: Option<SegatoolsTrustStatus>,
+    /// Files actually written by this deploy.
+    pub changed_files: Vec<String>,
 }
 
+/// Progress of a deploy: `stage` is `download` (bytes) or `extract` (archive entries).
+#[derive(Debug, Clone, Serialize)]
+pub struct DeployProgress {
+    pub stage: String,
+    pub current: u64,
+    pub total: u64,
+}
+
+const DOWNLOAD_PROGRESS_STEP: u64 = 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct RollbackResult {
     pub restored: bool,
@@ -224,6 +262,70 @@ struct DownloadedArtifact {
     path: NamedTempFile,
 }
 
+/// Runtime overrides for the trusted source, e.g. for self-hosted mirrors. The manifest
+/// signature is still checked, so a mirror can only serve manifests signed by the trusted key.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedOverrides {
+    pub base_url: Option<String>,
+    pub prefix: Option<String>,
+    /// Minisign public key replacing the bundled one, either the full `.pub` file
+    /// contents or just its base64 line.
+    pub public_key: Option<String>,
+    /// Number of segatools backups kept per game (default 3).
+    pub backup_generations: Option<usize>,
+}
+
+static TRUSTED_OVERRIDES: OnceLock<Mutex<TrustedOverrides>> = OnceLock::new();
+
+fn trusted_overrides() -> &'static Mutex<TrustedOverrides> {
+    TRUSTED_OVERRIDES.get_or_init(|| Mutex::new(TrustedOverrides::default()))
+}
+
+pub fn set_trusted_overrides(overrides: TrustedOverrides) {
+    if let Ok(mut current) = trusted_overrides().lock() {
+        *current = overrides;
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+fn trusted_base() -> String {
+    let base = trusted_overrides().lock().ok().and_then(|o| o.base_url.clone());
+    non_empty(base).unwrap_or_else(|| TRUSTED_BASE.to_string())
+}
+
+fn trusted_prefix() -> String {
+    let prefix = trusted_overrides().lock().ok().and_then(|o| o.prefix.clone());
+    non_empty(prefix)
+        .map(|p| p.trim_matches('/').to_string())
+        .unwrap_or_else(|| TRUSTED_PREFIX.to_string())
+}
+
+fn backup_generations() -> usize {
+    trusted_overrides()
+        .lock()
+        .ok()
+        .and_then(|o| o.backup_generations)
+        .unwrap_or(DEFAULT_BACKUP_GENERATIONS)
+        .max(1)
+}
+
+fn trusted_public_key() -> Result<PublicKey, TrustedError> {
+    let custom = trusted_overrides().lock().ok().and_then(|o| o.public_key.clone());
+    let Some(custom) = non_empty(custom) else {
+        return Ok(PublicKey::decode(PUBLIC_KEY)?);
+    };
+    let custom = custom.trim();
+    let decoded = if custom.contains('\n') {
+        PublicKey::decode(custom)
+    } else {
+        PublicKey::from_base64(custom)
+    };
+    decoded.map_err(|e| TrustedError::Verification(format!("Invalid trusted public key override: {}", e)))
+}
+
 #[derive(Clone)]
 struct CachedTrustEntry {
     status: SegatoolsTrustStatus,
@@ -237,8 +339,17 @@ fn trust_cache() -> &'static Mutex<HashMap<String, CachedTrustEntry>> {
     TRUST_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
+/// Key for the in-memory trust cache. Different spellings of the same directory
+/// (separators, casing on Windows, relative segments) map to one entry.
 fn cache_key(root: &Path) -> String {
-    root.to_string_lossy().to_string()
+    let resolved = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let key = resolved.to_string_lossy().replace('\\', "/");
+    let key = key.trim_end_matches('/');
+    if cfg!(windows) {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -267,7 +378,9 @@ fn trust_cache_path(root: &Path) -> PathBuf {
 fn capture_mtimes(root: &Path, files: &[FileCheckResult]) -> HashMap<String, u128> {
     let mut mtimes = HashMap::new();
     for file in files {
-        let path = root.join(&file.path);
+        let Some(path) = safe_join(root, &file.path) else {
+            continue;
+        };
         if let Some(modified) = file_mtime_nanos(&path) {
             mtimes.insert(file.path.clone(), modified);
         }
@@ -277,7 +390,9 @@ fn capture_mtimes(root: &Path, files: &[FileCheckResult]) -> HashMap<String, u12
 
 fn files_unchanged(root: &Path, mtimes: &HashMap<String, u128>) -> bool {
     for (rel, cached_time) in mtimes {
-        let path = root.join(rel);
+        let Some(path) = safe_join(root, rel) else {
+            return false;
+        };
         let modified = match file_mtime_nanos(&path) {
             Some(m) => m,
             None => return false,
@@ -388,29 +503,25 @@ fn store_status_for(root: &Path, status: &SegatoolsTrustStatus) {
 }
 
 fn client() -> Result<Client, TrustedError> {
-    Client::builder()
-        .timeout(Duration::from_secs(TRUST_TIMEOUT_SECS))
-        .connect_timeout(Duration::from_secs(TRUST_CONNECT_TIMEOUT_SECS))
-        .no_proxy()
-        .user_agent("ConfigArcLauncher/TrustedSupplychain")
-        .build()
+    build_client(HttpOptions::download().with_user_agent("ConfigArcLauncher/TrustedSupplychain"))
         .map_err(|e| TrustedError::Network(e.to_string()))
 }
 
 fn trusted_url(path: &str) -> String {
-    let base = TRUSTED_BASE.trim_end_matches('/');
+    let base = trusted_base();
+    let base = base.trim_end_matches('/');
     let trimmed = path.trim_start_matches('/');
     format!("{}/{}", base, trimmed)
 }
 
 fn manifest_url() -> String {
-    trusted_url(&format!("{}/{}/{}", TRUSTED_PREFIX, "latest", MANIFEST_NAME))
+    trusted_url(&format!("{}/{}/{}", trusted_prefix(), "latest", MANIFEST_NAME))
 }
 
 fn manifest_sig_url() -> String {
     trusted_url(&format!(
         "{}/{}/{}.minisig",
-        TRUSTED_PREFIX, "latest", MANIFEST_NAME
+        trusted_prefix(), "latest", MANIFEST_NAME
     ))
 }
 
@@ -427,23 +538,68 @@ fn download_bytes(url: &str) -> Result<Vec<u8>, TrustedError> {
     Ok(bytes.to_vec())
 }
 
-fn verify_manifest_signature(manifest_bytes: &[u8], sig_bytes: &[u8]) -> Result<(), TrustedError> {
+fn verify_manifest_signature(
+    pk: &PublicKey,
+    manifest_bytes: &[u8],
+    sig_bytes: &[u8],
+) -> Result<(), TrustedError> {
     let sig_str = std::str::from_utf8(sig_bytes)
         .map_err(|e| TrustedError::Verification(format!("Invalid signature utf8: {}", e)))?;
-    let pk = PublicKey::decode(PUBLIC_KEY)?;
     let sig = Signature::decode(sig_str)?;
     pk.verify(manifest_bytes, &sig, true)?;
     Ok(())
 }
 
 fn fetch_manifest() -> Result<TrustedManifest, TrustedError> {
+    // Resolve the key first so a malformed override fails before any download.
+    let pk = trusted_public_key()?;
     let manifest_bytes = download_bytes(&manifest_url())?;
     let sig_bytes = download_bytes(&manifest_sig_url())?;
-    verify_manifest_signature(&manifest_bytes, &sig_bytes)?;
+    verify_manifest_signature(&pk, &manifest_bytes, &sig_bytes)?;
     let manifest: TrustedManifest = serde_json::from_slice(&manifest_bytes)?;
+    write_manifest_cache(&manifest_bytes, &sig_bytes);
     Ok(manifest)
 }
 
+fn manifest_cache_paths() -> (PathBuf, PathBuf) {
+    let manifest = app_root_dir().join(MANIFEST_CACHE_NAME);
+    let sig = app_root_dir().join(format!("{}.minisig", MANIFEST_CACHE_NAME));
+    (manifest, sig)
+}
+
+fn write_manifest_cache(manifest_bytes: &[u8], sig_bytes: &[u8]) {
+    let (manifest_path, sig_path) = manifest_cache_paths();
+    let _ = fs::write(manifest_path, manifest_bytes);
+    let _ = fs::write(sig_path, sig_bytes);
+}
+
+/// The last manifest fetched successfully. Its signature is checked again on every read,
+/// so a tampered cache is rejected just like a tampered download.
+fn cached_manifest() -> Result<TrustedManifest, TrustedError> {
+    let (manifest_path, sig_path) = manifest_cache_paths();
+    if !manifest_path.exists() || !sig_path.exists() {
+        return Err(TrustedError::NotFound("No cached trusted manifest".to_string()));
+    }
+    let manifest_bytes = fs::read(manifest_path)?;
+    let sig_bytes = fs::read(sig_path)?;
+    verify_manifest_signature(&trusted_public_key()?, &manifest_bytes, &sig_bytes)?;
+    let manifest: TrustedManifest = serde_json::from_slice(&manifest_bytes)?;
+    Ok(manifest)
+}
+
+/// Fetches the manifest, falling back to the cached copy when the network is unavailable.
+/// The flag is true when the cached copy was used.
+fn fetch_manifest_or_cached() -> Result<(TrustedManifest, bool), TrustedError> {
+    match fetch_manifest() {
+        Ok(manifest) => Ok((manifest, false)),
+        Err(TrustedError::Network(err)) => match cached_manifest() {
+            Ok(manifest) => Ok((manifest, true)),
+            Err(_) => Err(TrustedError::Network(err)),
+        },
+        Err(err) => Err(err),
+    }
+}
+
 fn active_game_ctx() -> Result<ActiveGameContext, TrustedError> {
     let id = get_active_game_id().map_err(|e| TrustedError::NotFound(e.to_string()))?;
     let active_id = id.ok_or_else(|| TrustedError::NotFound("No active game selected".to_string()))?;
@@ -464,35 +620,66 @@ fn canonical_game_name(name: &str) -> String {
     lower
 }
 
-fn artifact_candidates(game: &Game) -> Vec<&'static str> {
-    match canonical_game_name(&game.name).as_str() {
-        "chunithm" => vec!["chusan.zip", "chuni.zip"],
-        "sinmai" => vec!["mai2.zip"],
-        "ongeki" => vec!["mu3.zip"],
-        _ => vec![],
+/// Games whose artifact name differs from their canonical name.
+const KNOWN_ARTIFACTS: &[(&str, &[&str])] = &[
+    ("chunithm", &["chusan.zip", "chuni.zip"]),
+    ("sinmai", &["mai2.zip"]),
+    ("ongeki", &["mu3.zip"]),
+];
+
+/// Artifact names to try, most specific first: the known aliases for the game, then
+/// `<name>.zip` for the canonical game name and each of its tags.
+fn artifact_candidates(game: &Game) -> Vec<String> {
+    let canonical = canonical_game_name(&game.name);
+    let mut candidates: Vec<String> = KNOWN_ARTIFACTS
+        .iter()
+        .filter(|(name, _)| *name == canonical)
+        .flat_map(|(_, artifacts)| artifacts.iter().map(|a| a.to_string()))
+        .collect();
+    let names = std::iter::once(canonical.clone()).chain(game.tags.iter().map(|t| canonical_game_name(t)));
+    for name in names {
+        if name.is_empty() {
+            continue;
+        }
+        let candidate = format!("{}.zip", name);
+        if !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
     }
+    candidates
 }
 
 fn select_artifact<'a>(
     manifest: &'a TrustedManifest,
     game: &Game,
 ) -> Result<&'a TrustedArtifact, TrustedError> {
-    let candidates = artifact_candidates(game);
-    for candidate in candidates {
-        if let Some(a) = manifest
-            .artifacts
-            .iter()
-            .find(|a| a.kind == "component" && a.name.eq_ignore_ascii_case(candidate))
-        {
+    let components: Vec<&TrustedArtifact> = manifest
+        .artifacts
+        .iter()
+        .filter(|a| a.kind == "component")
+        .collect();
+    for candidate in artifact_candidates(game) {
+        if let Some(a) = components.iter().find(|a| a.name.eq_ignore_ascii_case(&candidate)) {
             return Ok(a);
         }
     }
+    let available: Vec<&str> = components.iter().map(|a| a.name.as_str()).collect();
     Err(TrustedError::NotFound(format!(
-        "No trusted artifact found for game {}",
-        game.name
+        "No trusted artifact found for game {} (available: {})",
+        game.name,
+        if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.join(", ")
+        }
     )))
 }
 
+/// Lowercase hex SHA-256 of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
 fn sha256_reader<R: Read>(mut reader: R) -> Result<String, TrustedError> {
     let mut hasher = Sha256::new();
     let mut buf = [0u8; 8192];
@@ -506,7 +693,49 @@ fn sha256_reader<R: Read>(mut reader: R) -> Result<String, TrustedError> {
     Ok(format!("{:x}", hasher.finalize()))
 }
 
-fn download_artifact(artifact: &TrustedArtifact) -> Result<DownloadedArtifact, TrustedError> {
+/// SHA-256s everything written through it, so large output is hashed without a second
+/// read pass. Built with [`HashingWriter::disabled`] it only forwards writes.
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Option<Sha256>,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Some(Sha256::new()),
+        }
+    }
+
+    pub fn disabled(inner: W) -> Self {
+        Self { inner, hasher: None }
+    }
+
+    /// Lowercase hex digest of the bytes written, or `None` when hashing was disabled.
+    pub fn finish(self) -> Option<String> {
+        self.hasher.map(|hasher| format!("{:x}", hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if let Some(hasher) = self.hasher.as_mut() {
+            hasher.update(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn download_artifact(
+    artifact: &TrustedArtifact,
+    progress: &mut dyn FnMut(DeployProgress),
+) -> Result<DownloadedArtifact, TrustedError> {
     let url = trusted_url(&artifact.r2_key);
     let mut resp = client()?.get(url).send()?;
     if !resp.status().is_success() {
@@ -518,10 +747,34 @@ fn download_artifact(artifact: &TrustedArtifact) -> Result<DownloadedArtifact, T
     }
 
     let mut tmp = NamedTempFile::new()?;
-    let _written = resp.copy_to(&mut tmp)?;
-
-    tmp.as_file_mut().seek(SeekFrom::Start(0))?;
-    let sha = sha256_reader(tmp.as_file_mut())?;
+    let total = resp.content_length().unwrap_or(artifact.size);
+    let mut writer = HashingWriter::new(std::io::BufWriter::new(tmp.as_file_mut()));
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut downloaded = 0u64;
+    let mut next_report = 0u64;
+    loop {
+        let read = resp.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buf[..read])?;
+        downloaded += read as u64;
+        if downloaded >= next_report {
+            progress(DeployProgress {
+                stage: "download".to_string(),
+                current: downloaded,
+                total,
+            });
+            next_report = downloaded + DOWNLOAD_PROGRESS_STEP;
+        }
+    }
+    writer.flush()?;
+    progress(DeployProgress {
+        stage: "download".to_string(),
+        current: downloaded,
+        total: total.max(downloaded),
+    });
+    let sha = writer.finish().unwrap_or_default();
     if !artifact.sha256.is_empty() && sha != artifact.sha256 {
         return Err(TrustedError::Verification(format!(
             "Artifact sha mismatch (expected {}, got {})",
@@ -532,7 +785,7 @@ fn download_artifact(artifact: &TrustedArtifact) -> Result<DownloadedArtifact, T
     Ok(DownloadedArtifact { path: tmp })
 }
 
-fn clean_entry_path(entry: &str) -> Option<String> {
+pub(crate) fn clean_entry_path(entry: &str) -> Option<String> {
     let normalized = entry.replace('\\', "/");
     if normalized.trim().is_empty() || normalized.ends_with('/') {
         return None;
@@ -548,6 +801,26 @@ fn clean_entry_path(entry: &str) -> Option<String> {
     }
 }
 
+/// Joins a manifest or archive path onto `root`, refusing anything that could resolve
+/// outside it (traversal, absolute or drive-qualified paths, alternate data streams).
+fn safe_join(root: &Path, entry: &str) -> Option<PathBuf> {
+    if entry.starts_with(['/', '\\']) {
+        return None;
+    }
+    let rel = clean_entry_path(entry)?;
+    if rel.contains(':') {
+        return None;
+    }
+    let rel_path = Path::new(&rel);
+    if !rel_path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(root.join(rel_path))
+}
+
 fn is_binary_path(path: &str) -> bool {
     let lower = path.to_lowercase();
     lower.ends_with(".dll") || lower.ends_with(".exe")
@@ -592,65 +865,126 @@ fn expected_files(
     ))
 }
 
-fn check_files(
-    root: &Path,
-    files: &[TrustedFile],
-    artifact: &TrustedArtifact,
-    manifest: &TrustedManifest,
-) -> SegatoolsTrustStatus {
-    let has_backup = root
-        .join(BACKUP_DIR)
-        .join(BACKUP_META_NAME)
-        .exists();
-    let mut results = Vec::new();
-    let mut max_mismatch_ts: Option<u32> = None;
-
-    for file in files {
-        let target = root.join(Path::new(&file.path));
-        if target.exists() {
-            let sha = fs::File::open(&target)
-                .and_then(|mut f| {
-                    let res = sha256_reader(&mut f);
-                    res.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
-                })
-                .ok();
-            let matches = sha.as_ref().map(|s| s == &file.sha256).unwrap_or(false);
-            
-            if !matches {
-                let lower = file.path.to_lowercase();
-                if lower.ends_with(".dll") || lower.ends_with(".exe") {
-                     if let Some(ts) = get_pe_timestamp(&target) {
-                         if max_mismatch_ts.map_or(true, |current| ts > current) {
-                             max_mismatch_ts = Some(ts);
-                         }
-                     }
-                }
-            }
+struct FileCheck {
+    result: FileCheckResult,
+    pe_timestamp: Option<u32>,
+    unsafe_path: bool,
+}
 
-            results.push(FileCheckResult {
+fn check_file(root: &Path, file: &TrustedFile) -> FileCheck {
+    let Some(target) = safe_join(root, &file.path) else {
+        // Never touched on disk; reported as a failed check so the artifact is not trusted.
+        return FileCheck {
+            result: FileCheckResult {
                 path: file.path.clone(),
                 expected_sha256: file.sha256.clone(),
-                actual_sha256: sha,
-                exists: true,
-                matches,
-            });
-        } else {
-            results.push(FileCheckResult {
+                actual_sha256: None,
+                exists: false,
+                matches: false,
+                build_time: None,
+                architecture: None,
+            },
+            pe_timestamp: None,
+            unsafe_path: true,
+        };
+    };
+    if !target.exists() {
+        return FileCheck {
+            result: FileCheckResult {
                 path: file.path.clone(),
                 expected_sha256: file.sha256.clone(),
                 actual_sha256: None,
                 exists: false,
                 matches: false,
-            });
-        }
+                build_time: None,
+                architecture: None,
+            },
+            pe_timestamp: None,
+            unsafe_path: false,
+        };
     }
 
+    let sha = fs::File::open(&target)
+        .and_then(|mut f| {
+            let res = sha256_reader(&mut f);
+            res.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
+        })
+        .ok();
+    let matches = sha.as_ref().map(|s| s == &file.sha256).unwrap_or(false);
+    let pe = if is_binary_path(&file.path) {
+        read_pe_header(&target)
+    } else {
+        None
+    };
+
+    FileCheck {
+        result: FileCheckResult {
+            path: file.path.clone(),
+            expected_sha256: file.sha256.clone(),
+            actual_sha256: sha,
+            exists: true,
+            matches,
+            build_time: pe.as_ref().map(|h| format_timestamp(h.timestamp)),
+            architecture: pe.as_ref().map(|h| pe_architecture(h.machine)),
+        },
+        pe_timestamp: pe.map(|h| h.timestamp),
+        unsafe_path: false,
+    }
+}
+
+const MAX_HASH_WORKERS: usize = 8;
+
+/// Hashes `files` on a bounded set of scoped threads. Each worker takes a contiguous
+/// chunk, so the results come back in input order.
+fn check_files_parallel(root: &Path, files: &[TrustedFile]) -> Vec<FileCheck> {
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_HASH_WORKERS)
+        .min(files.len().max(1));
+    if workers <= 1 {
+        return files.iter().map(|file| check_file(root, file)).collect();
+    }
+    let chunk_size = files.len().div_ceil(workers);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || chunk.iter().map(|file| check_file(root, file)).collect::<Vec<_>>())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+fn check_files(
+    root: &Path,
+    files: &[TrustedFile],
+    artifact: &TrustedArtifact,
+    manifest: &TrustedManifest,
+) -> SegatoolsTrustStatus {
+    let has_backup = !list_backups(&root.join(BACKUP_DIR)).is_empty();
+    let checks = check_files_parallel(root, files);
+
+    let unsafe_paths = checks.iter().any(|c| c.unsafe_path);
+    let max_mismatch_ts = checks
+        .iter()
+        .filter(|c| !c.result.matches)
+        .filter_map(|c| c.pe_timestamp)
+        .max();
+    let results: Vec<FileCheckResult> = checks.into_iter().map(|c| c.result).collect();
+
     let local_build_time = max_mismatch_ts.map(format_timestamp);
 
     let missing_files = results.iter().any(|r| !r.exists);
     let all_match = !results.is_empty() && results.iter().all(|r| r.matches);
     let reason = if results.is_empty() {
         Some("No trusted DLL hashes available to verify this artifact".to_string())
+    } else if unsafe_paths {
+        Some("Trusted manifest lists file paths outside the game directory".to_string())
     } else if all_match {
         None
     } else if missing_files {
@@ -673,23 +1007,35 @@ fn check_files(
     }
 }
 
-pub fn verify_segatoools_for_active() -> Result<SegatoolsTrustStatus, TrustedError> {
+/// Checks the active game's segatools binaries against the trusted manifest. Unless
+/// `force` is set, a fresh cached status is returned while the files are unchanged.
+pub fn verify_segatoools_for_active(force: bool) -> Result<SegatoolsTrustStatus, TrustedError> {
     let ctx = active_game_ctx()?;
 
-    if let Some(cached) = cached_status_for(&ctx.root) {
+    if force {
+        clear_cached_status(&ctx.root);
+    } else if let Some(cached) = cached_status_for(&ctx.root) {
         return Ok(cached);
     }
 
-    let manifest = fetch_manifest()?;
+    let (manifest, offline) = fetch_manifest_or_cached()?;
     let artifact = select_artifact(&manifest, &ctx.game)?;
     let downloaded = if artifact.files.is_empty() {
-        Some(download_artifact(artifact)?)
+        Some(download_artifact(artifact, &mut |_| {})?)
     } else {
         None
     };
     let expected = expected_files(artifact, downloaded.as_ref())?;
-    let status = check_files(&ctx.root, &expected, artifact, &manifest);
-    store_status_for(&ctx.root, &status);
+    let mut status = check_files(&ctx.root, &expected, artifact, &manifest);
+    if offline {
+        // Not cached, so the next check goes back to the network.
+        status.reason = Some(match status.reason.take() {
+            Some(reason) => format!("{} (verified offline against cached manifest)", reason),
+            None => "Verified offline against cached manifest".to_string(),
+        });
+    } else {
+        store_status_for(&ctx.root, &status);
+    }
     Ok(status)
 }
 
@@ -715,16 +1061,81 @@ fn ensure_parent(path: &Path) -> Result<(), TrustedError> {
     Ok(())
 }
 
+fn read_backup_metadata(backup_dir: &Path) -> Result<Option<BackupMetadata>, TrustedError> {
+    let meta_path = backup_dir.join(BACKUP_META_NAME);
+    if !meta_path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(&fs::read(&meta_path)?)?))
+}
+
+/// Generation id a single-generation backup is given when it is migrated.
+fn legacy_backup_id(meta: &BackupMetadata) -> String {
+    chrono::DateTime::parse_from_rfc3339(&meta.created_at)
+        .map(|t| t.with_timezone(&Utc).format("%Y%m%d-%H%M%S-000").to_string())
+        .unwrap_or_else(|_| "legacy".to_string())
+}
+
+/// Moves a single-generation backup (`Segatools_Backup/metadata.json`) into its own
+/// generation directory. Run before a new backup is written.
+fn migrate_legacy_backup(backup_root: &Path) -> Result<(), TrustedError> {
+    let Some(meta) = read_backup_metadata(backup_root)? else {
+        return Ok(());
+    };
+    let target = backup_root.join(legacy_backup_id(&meta));
+    fs::create_dir_all(&target)?;
+    let files_dir = backup_root.join(BACKUP_FILES_DIR);
+    if files_dir.exists() {
+        fs::rename(&files_dir, target.join(BACKUP_FILES_DIR))?;
+    }
+    fs::rename(backup_root.join(BACKUP_META_NAME), target.join(BACKUP_META_NAME))?;
+    Ok(())
+}
+
+/// Backup generations under `backup_root`, newest first. A single-generation backup that
+/// has not been migrated yet is listed in place, under the id migration will give it.
+fn list_backups(backup_root: &Path) -> Vec<(PathBuf, BackupMetadata)> {
+    let Ok(dirs) = fs::read_dir(backup_root) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<(PathBuf, BackupMetadata)> = dirs
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let mut meta = read_backup_metadata(&dir).ok().flatten()?;
+            meta.id = entry.file_name().to_string_lossy().to_string();
+            Some((dir, meta))
+        })
+        .collect();
+    if let Ok(Some(mut meta)) = read_backup_metadata(backup_root) {
+        meta.id = legacy_backup_id(&meta);
+        backups.push((backup_root.to_path_buf(), meta));
+    }
+    backups.sort_by(|a, b| b.1.id.cmp(&a.1.id));
+    backups
+}
+
+fn prune_backups(backup_root: &Path) {
+    for (dir, _) in list_backups(backup_root).into_iter().skip(backup_generations()) {
+        // An unmigrated backup lives in `backup_root` itself, next to every generation.
+        if dir != backup_root {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
 fn backup_existing(
     root: &Path,
     entries: &[String],
     artifact: &TrustedArtifact,
     manifest: &TrustedManifest,
 ) -> Result<(PathBuf, BackupMetadata), TrustedError> {
-    let backup_root = root.join(BACKUP_DIR);
-    if backup_root.exists() {
-        fs::remove_dir_all(&backup_root)?;
-    }
+    let parent = root.join(BACKUP_DIR);
+    let _ = migrate_legacy_backup(&parent);
+    let now = Utc::now();
+    let id = now.format("%Y%m%d-%H%M%S-%3f").to_string();
+    let backup_root = parent.join(&id);
     let files_dir = backup_root.join(BACKUP_FILES_DIR);
     fs::create_dir_all(&files_dir)?;
 
@@ -732,9 +1143,10 @@ fn backup_existing(
     let mut new_files = Vec::new();
 
     for entry in entries {
-        let target = root.join(entry);
+        let (Some(target), Some(backup_target)) = (safe_join(root, entry), safe_join(&files_dir, entry)) else {
+            continue;
+        };
         if target.exists() {
-            let backup_target = files_dir.join(entry);
             ensure_parent(&backup_target)?;
             fs::copy(&target, &backup_target)?;
             backed_up.push(entry.clone());
@@ -744,7 +1156,8 @@ fn backup_existing(
     }
 
     let metadata = BackupMetadata {
-        created_at: Utc::now().to_rfc3339(),
+        id,
+        created_at: now.to_rfc3339(),
         artifact_name: artifact.name.clone(),
         artifact_sha256: artifact.sha256.clone(),
         build_id: Some(manifest.build_id.clone()),
@@ -755,37 +1168,125 @@ fn backup_existing(
     let meta_path = backup_root.join(BACKUP_META_NAME);
     let meta_json = serde_json::to_string_pretty(&metadata)?;
     fs::write(meta_path, meta_json)?;
+    prune_backups(&parent);
 
     Ok((backup_root, metadata))
 }
 
-fn extract_artifact(root: &Path, path: &Path) -> Result<(), TrustedError> {
+/// Entries whose on-disk copy already matches the artifact. Files covered by the trusted
+/// file list are compared through `check_files`; anything else is hashed against the zip.
+fn unchanged_entries(
+    root: &Path,
+    path: &Path,
+    expected: &[TrustedFile],
+    artifact: &TrustedArtifact,
+    manifest: &TrustedManifest,
+) -> Result<HashSet<String>, TrustedError> {
+    let status = check_files(root, expected, artifact, manifest);
+    let mut unchanged: HashSet<String> = status
+        .checked_files
+        .iter()
+        .filter(|f| f.matches)
+        .map(|f| f.path.to_lowercase())
+        .collect();
+    let covered: HashSet<String> = expected.iter().map(|f| f.path.to_lowercase()).collect();
+
+    let file = fs::File::open(path)?;
+    let mut zip = ZipArchive::new(file)?;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(name) = clean_entry_path(entry.name()) else {
+            continue;
+        };
+        let key = name.to_lowercase();
+        if covered.contains(&key) {
+            continue;
+        }
+        let Some(target) = safe_join(root, &name) else {
+            continue;
+        };
+        if !target.exists() {
+            continue;
+        }
+        let on_disk = sha256_reader(fs::File::open(&target)?)?;
+        if sha256_reader(&mut entry)? == on_disk {
+            unchanged.insert(key);
+        }
+    }
+    Ok(unchanged)
+}
+
+fn extract_artifact(
+    root: &Path,
+    path: &Path,
+    unchanged: &HashSet<String>,
+    progress: &mut dyn FnMut(DeployProgress),
+) -> Result<Vec<String>, TrustedError> {
     let file = fs::File::open(path)?;
     let mut zip = ZipArchive::new(file)?;
+    let total = zip.len() as u64;
+    let mut written = Vec::new();
     for i in 0..zip.len() {
+        progress(DeployProgress {
+            stage: "extract".to_string(),
+            current: i as u64 + 1,
+            total,
+        });
         let mut entry = zip.by_index(i)?;
         if let Some(name) = clean_entry_path(entry.name()) {
-            let target = root.join(&name);
+            if unchanged.contains(&name.to_lowercase()) {
+                continue;
+            }
+            let Some(target) = safe_join(root, &name) else {
+                continue;
+            };
             ensure_parent(&target)?;
             let mut out = fs::File::create(&target)?;
             std::io::copy(&mut entry, &mut out)?;
+            drop(out);
+            if name.eq_ignore_ascii_case("segatools.ini") {
+                crate::config::record_segatoools_hash(&target);
+            }
+            written.push(name);
         }
     }
-    Ok(())
+    Ok(written)
 }
 
-pub fn deploy_segatoools_for_active(force: bool) -> Result<DeployResult, TrustedError> {
+/// Deploys the trusted segatools artifact for the active game. With `incremental`, files
+/// whose on-disk hash already matches are neither backed up nor rewritten.
+pub fn deploy_segatoools_for_active(
+    force: bool,
+    incremental: bool,
+    mut progress: Option<&mut dyn FnMut(DeployProgress)>,
+) -> Result<DeployResult, TrustedError> {
+    let mut report = |event: DeployProgress| {
+        if let Some(cb) = progress.as_mut() {
+            cb(event);
+        }
+    };
     let ctx = active_game_ctx()?;
     let manifest = fetch_manifest()?;
     let artifact = select_artifact(&manifest, &ctx.game)?;
-    let downloaded = download_artifact(artifact)?;
-    let entries = collect_zip_entries(downloaded.path.path())?;
+    let downloaded = download_artifact(artifact, &mut report)?;
+    let expected = expected_files(artifact, Some(&downloaded))?;
+    let unchanged = if incremental {
+        unchanged_entries(&ctx.root, downloaded.path.path(), &expected, artifact, &manifest)?
+    } else {
+        HashSet::new()
+    };
+    let entries: Vec<String> = collect_zip_entries(downloaded.path.path())?
+        .into_iter()
+        .filter(|rel| !unchanged.contains(&rel.to_lowercase()))
+        .collect();
     let existing: Vec<String> = entries
         .iter()
-        .filter(|rel| ctx.root.join(rel).exists())
+        .filter(|rel| safe_join(&ctx.root, rel).is_some_and(|p| p.exists()))
         .cloned()
         .collect();
-    let has_backup = !existing.is_empty();
 
     if !existing.is_empty() && !force {
         return Ok(DeployResult {
@@ -795,15 +1296,18 @@ pub fn deploy_segatoools_for_active(force: bool) -> Result<DeployResult, Trusted
             backup_dir: None,
             message: Some("Existing segatools files detected. Backup and confirmation required.".to_string()),
             verification: None,
+            changed_files: vec![],
         });
     }
 
-    if !existing.is_empty() {
-        let _ = backup_existing(&ctx.root, &entries, artifact, &manifest)?;
-    }
+    let backup_dir = if existing.is_empty() {
+        None
+    } else {
+        let (dir, _) = backup_existing(&ctx.root, &entries, artifact, &manifest)?;
+        Some(dir.to_string_lossy().to_string())
+    };
 
-    extract_artifact(&ctx.root, downloaded.path.path())?;
-    let expected = expected_files(artifact, Some(&downloaded))?;
+    let changed_files = extract_artifact(&ctx.root, downloaded.path.path(), &unchanged, &mut report)?;
     let verification = check_files(&ctx.root, &expected, artifact, &manifest);
     store_status_for(&ctx.root, &verification);
 
@@ -811,47 +1315,284 @@ pub fn deploy_segatoools_for_active(force: bool) -> Result<DeployResult, Trusted
         deployed: true,
         needs_confirmation: false,
         existing_files: existing,
-        backup_dir: if has_backup {
-            Some(ctx.root.join(BACKUP_DIR).to_string_lossy().to_string())
+        backup_dir,
+        message: Some(if changed_files.is_empty() {
+            "segatools already up to date".to_string()
         } else {
-            None
-        },
-        message: Some("segatools deployed successfully".to_string()),
+            "segatools deployed successfully".to_string()
+        }),
         verification: Some(verification),
+        changed_files,
     })
 }
 
-pub fn rollback_segatoools_for_active() -> Result<RollbackResult, TrustedError> {
+/// Segatools backups for the active game, newest first.
+pub fn backups_for_active() -> Result<Vec<BackupMetadata>, TrustedError> {
     let ctx = active_game_ctx()?;
-    let backup_root = ctx.root.join(BACKUP_DIR);
-    let meta_path = backup_root.join(BACKUP_META_NAME);
-    if !meta_path.exists() {
-        return Err(TrustedError::NotFound(
-            "No segatools backup available to roll back".to_string(),
-        ));
-    }
-    let meta: BackupMetadata = serde_json::from_slice(&fs::read(&meta_path)?)?;
+    Ok(list_backups(&ctx.root.join(BACKUP_DIR))
+        .into_iter()
+        .map(|(_, meta)| meta)
+        .collect())
+}
+
+/// Restores the backup `backup_id`, or the most recent one when `None`.
+pub fn rollback_segatoools_for_active(backup_id: Option<&str>) -> Result<RollbackResult, TrustedError> {
+    let ctx = active_game_ctx()?;
+    let backups = list_backups(&ctx.root.join(BACKUP_DIR));
+    let (backup_root, meta) = match backup_id {
+        Some(id) => backups.into_iter().find(|(_, meta)| meta.id == id).ok_or_else(|| {
+            TrustedError::NotFound(format!("Segatools backup {} not found", id))
+        })?,
+        None => backups.into_iter().next().ok_or_else(|| {
+            TrustedError::NotFound("No segatools backup available to roll back".to_string())
+        })?,
+    };
 
     clear_cached_status(&ctx.root);
+    restore_backup(&ctx.root, &backup_root, &meta)?;
+    let verification = verify_segatoools_for_active(false).ok();
+
+    Ok(RollbackResult {
+        restored: true,
+        message: Some(format!("Restored segatools from backup {}", meta.id)),
+        verification,
+    })
+}
+
+/// Copies the files saved in `backup_root` back into `root` and removes the files the
+/// backed-up deploy added. Entries that would resolve outside either directory are skipped.
+fn restore_backup(root: &Path, backup_root: &Path, meta: &BackupMetadata) -> Result<(), TrustedError> {
+    let files_dir = backup_root.join(BACKUP_FILES_DIR);
     for file in &meta.backed_up_files {
-        let backup_path = backup_root.join(BACKUP_FILES_DIR).join(file);
-        let target = ctx.root.join(file);
+        let (Some(backup_path), Some(target)) = (safe_join(&files_dir, file), safe_join(root, file)) else {
+            continue;
+        };
         ensure_parent(&target)?;
         fs::copy(&backup_path, &target)?;
+        if file.eq_ignore_ascii_case("segatools.ini") {
+            crate::config::record_segatoools_hash(&target);
+        }
     }
 
     for file in &meta.new_files {
-        let target = ctx.root.join(file);
+        let Some(target) = safe_join(root, file) else {
+            continue;
+        };
         if target.exists() {
             let _ = fs::remove_file(&target);
         }
     }
+    Ok(())
+}
 
-    let verification = verify_segatoools_for_active().ok();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_key_matches_spellings_of_one_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let key = cache_key(dir.path());
+        let trailing = format!("{}/", dir.path().display());
+        assert_eq!(cache_key(Path::new(&trailing)), key);
+        assert_eq!(cache_key(&dir.path().join("sub").join("..")), key);
+        assert_ne!(cache_key(&dir.path().join("sub")), key);
+    }
 
-    Ok(RollbackResult {
-        restored: true,
-        message: Some("Restored segatools from backup".to_string()),
-        verification,
-    })
+    #[test]
+    fn safe_join_keeps_relative_paths() {
+        let root = Path::new("root");
+        assert_eq!(safe_join(root, "a/b.dll"), Some(root.join("a").join("b.dll")));
+        assert_eq!(safe_join(root, "a\\b.dll"), Some(root.join("a").join("b.dll")));
+    }
+
+    #[test]
+    fn safe_join_rejects_traversal() {
+        let root = Path::new("root");
+        assert_eq!(safe_join(root, "../b.dll"), None);
+        assert_eq!(safe_join(root, "a/../../b.dll"), None);
+        assert_eq!(safe_join(root, "a\\..\\b.dll"), None);
+        assert_eq!(safe_join(root, "./b.dll"), None);
+    }
+
+    #[test]
+    fn safe_join_rejects_absolute_paths() {
+        let root = Path::new("root");
+        assert_eq!(safe_join(root, "/etc/b.dll"), None);
+        assert_eq!(safe_join(root, "\\b.dll"), None);
+        assert_eq!(safe_join(root, "\\\\server\\share\\b.dll"), None);
+    }
+
+    #[test]
+    fn safe_join_rejects_drive_prefixes_and_streams() {
+        let root = Path::new("root");
+        assert_eq!(safe_join(root, "C:\\b.dll"), None);
+        assert_eq!(safe_join(root, "C:b.dll"), None);
+        assert_eq!(safe_join(root, "b.dll:stream"), None);
+    }
+
+    /// A game root inside a temp dir, with `x.dll` placed beside (outside) the root.
+    fn root_with_outside_file() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let base = tempfile::tempdir().unwrap();
+        let root = base.path().join("root");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("ok.dll"), b"ok").unwrap();
+        let outside = base.path().join("x.dll");
+        fs::write(&outside, b"outside").unwrap();
+        (base, root, outside)
+    }
+
+    fn malicious_entries(outside: &Path) -> Vec<String> {
+        vec![
+            "../x.dll".to_string(),
+            "..\\x.dll".to_string(),
+            "C:\\x.dll".to_string(),
+            outside.to_string_lossy().to_string(),
+        ]
+    }
+
+    fn test_manifest() -> (TrustedArtifact, TrustedManifest) {
+        let manifest: TrustedManifest = serde_json::from_value(serde_json::json!({
+            "build_id": "test",
+            "artifacts": [{ "kind": "chusan", "name": "test.zip", "r2_key": "test.zip" }],
+        }))
+        .unwrap();
+        (manifest.artifacts[0].clone(), manifest)
+    }
+
+    #[test]
+    fn check_files_skips_malicious_entries() {
+        let (_base, root, outside) = root_with_outside_file();
+        let (artifact, manifest) = test_manifest();
+        let mut files: Vec<TrustedFile> = malicious_entries(&outside)
+            .into_iter()
+            .map(|path| TrustedFile {
+                path,
+                size: 7,
+                sha256: sha256_hex(b"outside"),
+            })
+            .collect();
+        files.push(TrustedFile {
+            path: "ok.dll".to_string(),
+            size: 2,
+            sha256: sha256_hex(b"ok"),
+        });
+
+        let status = check_files(&root, &files, &artifact, &manifest);
+        assert!(!status.trusted);
+        let (ok, unsafe_files) = status.checked_files.split_last().unwrap();
+        assert!(ok.matches);
+        assert!(unsafe_files.iter().all(|f| !f.exists && f.actual_sha256.is_none()));
+        assert_eq!(
+            status.reason.as_deref(),
+            Some("Trusted manifest lists file paths outside the game directory")
+        );
+    }
+
+    #[test]
+    fn backup_existing_skips_malicious_entries() {
+        let (_base, root, outside) = root_with_outside_file();
+        let (artifact, manifest) = test_manifest();
+        let mut entries = malicious_entries(&outside);
+        entries.push("ok.dll".to_string());
+
+        let (backup_root, meta) = backup_existing(&root, &entries, &artifact, &manifest).unwrap();
+        assert_eq!(meta.backed_up_files, vec!["ok.dll".to_string()]);
+        assert!(meta.new_files.is_empty());
+        let saved: Vec<_> = fs::read_dir(backup_root.join(BACKUP_FILES_DIR))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(saved, vec!["ok.dll"]);
+        assert!(!backup_root.join("x.dll").exists());
+    }
+
+    #[test]
+    fn restore_backup_skips_malicious_entries() {
+        let (base, root, outside) = root_with_outside_file();
+        let backup_root = root.join(BACKUP_DIR).join("gen");
+        let files_dir = backup_root.join(BACKUP_FILES_DIR);
+        fs::create_dir_all(&files_dir).unwrap();
+        fs::write(files_dir.join("ok.dll"), b"restored").unwrap();
+        // What `files/../x.dll` would resolve to inside the backup.
+        fs::write(backup_root.join("x.dll"), b"backup").unwrap();
+        let added = base.path().join("added.dll");
+        fs::write(&added, b"outside").unwrap();
+
+        let mut backed_up_files = malicious_entries(&outside);
+        backed_up_files.push("ok.dll".to_string());
+        let meta = BackupMetadata {
+            id: "gen".to_string(),
+            created_at: String::new(),
+            artifact_name: String::new(),
+            artifact_sha256: String::new(),
+            build_id: None,
+            backed_up_files,
+            new_files: vec!["../added.dll".to_string(), added.to_string_lossy().to_string()],
+        };
+
+        restore_backup(&root, &backup_root, &meta).unwrap();
+        assert_eq!(fs::read(root.join("ok.dll")).unwrap(), b"restored");
+        assert_eq!(fs::read(&outside).unwrap(), b"outside");
+        assert!(added.exists());
+    }
+
+    #[test]
+    fn legacy_backup_is_listed_in_place_and_migrated_by_backup() {
+        let (_base, root, _) = root_with_outside_file();
+        let backup_dir = root.join(BACKUP_DIR);
+        fs::create_dir_all(backup_dir.join(BACKUP_FILES_DIR)).unwrap();
+        fs::write(
+            backup_dir.join(BACKUP_META_NAME),
+            br#"{"created_at":"2024-01-02T03:04:05Z","artifact_name":"a","artifact_sha256":"",
+                "build_id":null,"backed_up_files":[],"new_files":[]}"#,
+        )
+        .unwrap();
+
+        let listed = list_backups(&backup_dir);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].0, backup_dir);
+        assert_eq!(listed[0].1.id, "20240102-030405-000");
+        assert!(backup_dir.join(BACKUP_META_NAME).exists());
+
+        let (artifact, manifest) = test_manifest();
+        backup_existing(&root, &["ok.dll".to_string()], &artifact, &manifest).unwrap();
+        assert!(!backup_dir.join(BACKUP_META_NAME).exists());
+        let ids: Vec<String> = list_backups(&backup_dir).into_iter().map(|(_, meta)| meta.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[1], "20240102-030405-000");
+        assert!(backup_dir.join(&ids[1]).join(BACKUP_FILES_DIR).is_dir());
+    }
+
+    // Signed with a throwaway key, not the bundled one.
+    const TEST_PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const TEST_MANIFEST: &[u8] = br#"{"files":[]}"#;
+    const TEST_SIGNATURE: &str = "untrusted comment: signature from test key
+RUQBAgMEBQYHCErsECqtoaRts+GWMZW1b5/n4RM5xu0sObwwFXaQHFzb8HQU2U1JC+Y/qdiherTH5urLLiZyGIaUwwKZWKxKogA=
+trusted comment: timestamp:0
+m/vbpUn4nkL2e9Q7ifizHyDla3JTJdlBR9pvHqgrILIgryn4122Msyv/7In1rpajNY8yRPRsXeqStdx45qvfAg==
+";
+
+    #[test]
+    fn public_key_override_replaces_bundled_key() {
+        let sig = TEST_SIGNATURE.as_bytes();
+        let bundled = trusted_public_key().unwrap();
+        assert!(verify_manifest_signature(&bundled, TEST_MANIFEST, sig).is_err());
+
+        set_trusted_overrides(TrustedOverrides {
+            public_key: Some(TEST_PUBLIC_KEY.to_string()),
+            ..Default::default()
+        });
+        let custom = trusted_public_key();
+        set_trusted_overrides(TrustedOverrides {
+            public_key: Some("not a key".to_string()),
+            ..Default::default()
+        });
+        let malformed = trusted_public_key();
+        set_trusted_overrides(TrustedOverrides::default());
+
+        assert!(verify_manifest_signature(&custom.unwrap(), TEST_MANIFEST, sig).is_ok());
+        assert!(matches!(malformed, Err(TrustedError::Verification(_))));
+    }
 }
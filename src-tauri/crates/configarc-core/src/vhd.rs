@@ -10,6 +10,11 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::thread::sleep;
 
 const CREATE_NO_WINDOW: u32 = 0x08000000;
+const DEFAULT_DRIVE_LETTER: char = 'X';
+const DEFAULT_HELPER_TIMEOUT_SECS: u64 = 60;
+const HELPER_LOG_TAIL_CHARS: usize = 2000;
+const STALE_REMOVE_ATTEMPTS: u32 = 5;
+const DRIVE_READY_TIMEOUT: Duration = Duration::from_secs(5);
 
 fn default_true() -> bool {
     true
@@ -21,6 +26,43 @@ pub struct VhdConfig {
     pub patch_path: String,
     #[serde(default = "default_true")]
     pub delta_enabled: bool,
+    #[serde(default)]
+    pub drive_letter: Option<char>,
+    #[serde(default)]
+    pub helper_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl Default for VhdConfig {
+    fn default() -> Self {
+        Self {
+            base_path: String::new(),
+            patch_path: String::new(),
+            delta_enabled: true,
+            drive_letter: None,
+            helper_timeout_secs: None,
+            read_only: false,
+        }
+    }
+}
+
+impl VhdConfig {
+    /// Describes risky settings that `resolve_vhd_config` still accepts; empty when none apply.
+    ///
+    /// The patch is a child of the base VHD. With `delta_enabled` off and `read_only` off it
+    /// is mounted read-write directly, so game writes land in the shared patch and base chain
+    /// instead of a throwaway runtime disk.
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.delta_enabled && !self.read_only && !self.patch_path.trim().is_empty() {
+            warnings.push(
+                "delta is disabled, so the patch VHD is mounted read-write and changes can corrupt the shared base chain"
+                    .to_string(),
+            );
+        }
+        warnings
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,12 +70,16 @@ pub struct ResolvedVhdConfig {
     pub base_path: PathBuf,
     pub patch_path: PathBuf,
     pub delta_enabled: bool,
+    pub read_only: bool,
+    pub drive_letter: char,
+    pub helper_timeout: Duration,
 }
 
 #[derive(Debug, Clone)]
 pub struct MountedVhd {
     pub mount_path: PathBuf,
     pub runtime_path: Option<PathBuf>,
+    pub drive_letter: char,
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +88,10 @@ pub struct ElevatedVhdMount {
     pub result_path: PathBuf,
     pub signal_path: PathBuf,
     pub done_path: PathBuf,
+    pub log_path: PathBuf,
+    pub mount_path: Option<PathBuf>,
+    pub runtime_path: Option<PathBuf>,
+    pub drive_letter: char,
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +100,23 @@ pub enum VhdMountHandle {
     Elevated(ElevatedVhdMount),
 }
 
+impl VhdMountHandle {
+    pub fn drive_letter(&self) -> char {
+        match self {
+            VhdMountHandle::Direct(mounted) => mounted.drive_letter,
+            VhdMountHandle::Elevated(mounted) => mounted.drive_letter,
+        }
+    }
+}
+
+/// A mount stage reported by the elevated helper, one JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VhdMountProgress {
+    pub stage: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct HelperResult {
     ok: bool,
@@ -91,21 +158,122 @@ pub fn resolve_vhd_config(game_id: &str, cfg: &VhdConfig) -> Result<ResolvedVhdC
         .ok_or_else(|| "Missing vhd.json parent directory".to_string())?;
     let base_path = resolve_with_base(&base_dir, &cfg.base_path);
     let patch_path = resolve_with_base(&base_dir, &cfg.patch_path);
+    let drive_letter = resolve_drive_letter(cfg.drive_letter)?;
 
+    if cfg.delta_enabled && cfg.read_only {
+        return Err("Read-only mounts cannot use a differencing disk; disable delta_enabled or read_only".to_string());
+    }
     if !base_path.exists() {
         return Err(format!("Base VHD not found: {}", base_path.to_string_lossy()));
     }
     if !patch_path.exists() {
         return Err(format!("Patch VHD not found: {}", patch_path.to_string_lossy()));
     }
+    if cfg.delta_enabled {
+        verify_patch_parent(&base_path, &patch_path)?;
+    }
 
     Ok(ResolvedVhdConfig {
         base_path,
         patch_path,
         delta_enabled: cfg.delta_enabled,
+        read_only: cfg.read_only,
+        drive_letter,
+        helper_timeout: Duration::from_secs(
+            cfg.helper_timeout_secs
+                .filter(|secs| *secs > 0)
+                .unwrap_or(DEFAULT_HELPER_TIMEOUT_SECS),
+        ),
     })
 }
 
+fn normalize_windows_path(path: &str) -> String {
+    path.trim()
+        .replace('/', "\\")
+        .trim_end_matches('\\')
+        .to_lowercase()
+}
+
+fn verify_patch_parent(base_path: &Path, patch_path: &Path) -> Result<(), String> {
+    let query = format!(
+        "(Get-VHD -Path {} -ErrorAction Stop).ParentPath",
+        ps_quote(&patch_path.to_string_lossy())
+    );
+    // Get-VHD needs the Hyper-V module; skip the check when it is unavailable.
+    let Ok(parent) = run_powershell_output(&query) else {
+        return Ok(());
+    };
+    let expected = base_path.to_string_lossy();
+    if parent.trim().is_empty() {
+        return Err(format!(
+            "Patch VHD is not a differencing disk (expected parent {}): {}",
+            expected,
+            patch_path.to_string_lossy()
+        ));
+    }
+    if normalize_windows_path(&parent) != normalize_windows_path(&expected) {
+        return Err(format!(
+            "Patch VHD parent mismatch: expected {}, actual {}",
+            expected,
+            parent.trim()
+        ));
+    }
+    Ok(())
+}
+
+fn resolve_drive_letter(raw: Option<char>) -> Result<char, String> {
+    let letter = raw.unwrap_or(DEFAULT_DRIVE_LETTER).to_ascii_uppercase();
+    if !letter.is_ascii_uppercase() {
+        return Err(format!("Invalid drive letter: {letter}"));
+    }
+    Ok(letter)
+}
+
+pub fn drive_root(letter: char) -> String {
+    format!("{letter}:\\")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriveLetterStatus {
+    pub letter: char,
+    pub in_use: bool,
+    /// Free and not A/B or the system drive.
+    pub recommended: bool,
+}
+
+/// Reports every letter A-Z, in order, for picking a mount drive letter.
+pub fn drive_letters() -> Vec<DriveLetterStatus> {
+    let system = std::env::var("SystemDrive")
+        .ok()
+        .and_then(|drive| drive.chars().next())
+        .map(|c| c.to_ascii_uppercase())
+        .unwrap_or('C');
+    ('A'..='Z')
+        .map(|letter| {
+            let in_use = Path::new(&drive_root(letter)).exists();
+            DriveLetterStatus {
+                letter,
+                in_use,
+                recommended: !in_use && !matches!(letter, 'A' | 'B') && letter != system,
+            }
+        })
+        .collect()
+}
+
+/// Waits for the root of a freshly mounted drive to become reachable; Windows can
+/// report the mount as done slightly before the volume answers path lookups.
+pub fn wait_for_drive(letter: char) -> Result<(), String> {
+    let root = drive_root(letter);
+    let start = Instant::now();
+    while !Path::new(&root).exists() {
+        if start.elapsed() >= DRIVE_READY_TIMEOUT {
+            return Err(format!("Drive {root} is not available after mounting"));
+        }
+        sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
 fn resolve_with_base(base: &Path, raw: &str) -> PathBuf {
     let path = PathBuf::from(raw);
     if path.is_absolute() {
@@ -126,13 +294,17 @@ fn runtime_path_for_patch(patch_path: &Path) -> PathBuf {
 }
 
 fn run_powershell(command: &str) -> Result<(), String> {
+    run_powershell_output(command).map(|_| ())
+}
+
+fn run_powershell_output(command: &str) -> Result<String, String> {
     let output = Command::new("powershell")
         .args(&["-NoProfile", "-Command", command])
         .creation_flags(CREATE_NO_WINDOW)
         .output()
         .map_err(|e| e.to_string())?;
     if output.status.success() {
-        return Ok(());
+        return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
     }
     let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
     let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -166,9 +338,23 @@ fn run_diskpart(script: &str) -> Result<(), String> {
     })
 }
 
-fn close_explorer_for_x_drive() {
-    let cmd = "Start-Sleep -Milliseconds 300; $shell = New-Object -ComObject Shell.Application; $shell.Windows() | Where-Object { $_.LocationURL -like 'file:///X:*' -or $_.LocationURL -like 'file:///X:/*' } | ForEach-Object { $_.Quit() }";
-    let _ = run_powershell(cmd);
+fn run_diskpart_elevated(script: &str) -> Result<(), String> {
+    let script_path = std::env::temp_dir().join(format!("configarc_vhd_diskpart_{}.txt", temp_tag()));
+    fs::write(&script_path, script.as_bytes()).map_err(|e| e.to_string())?;
+    let cmd = format!(
+        "$p = Start-Process -Verb RunAs -WindowStyle Hidden -Wait -PassThru -FilePath diskpart.exe -ArgumentList @('/s', {}); exit $p.ExitCode",
+        ps_quote(&script_path.to_string_lossy())
+    );
+    let result = run_powershell(&cmd);
+    let _ = fs::remove_file(&script_path);
+    result
+}
+
+fn close_explorer_for_drive(letter: char) {
+    let cmd = format!(
+        "Start-Sleep -Milliseconds 300; $shell = New-Object -ComObject Shell.Application; $shell.Windows() | Where-Object {{ $_.LocationURL -like 'file:///{letter}:*' -or $_.LocationURL -like 'file:///{letter}:/*' }} | ForEach-Object {{ $_.Quit() }}"
+    );
+    let _ = run_powershell(&cmd);
 }
 
 #[cfg(target_os = "windows")]
@@ -200,17 +386,49 @@ fn temp_tag() -> String {
         .to_string()
 }
 
-fn ensure_x_drive_free() -> Result<(), String> {
-    if Path::new("X:\\").exists() {
-        return Err("Drive X: is already in use. Please eject or change the assigned drive.".to_string());
+pub fn ensure_drive_free(letter: char) -> Result<(), String> {
+    if Path::new(&drive_root(letter)).exists() {
+        return Err(format!(
+            "Drive {letter}: is already in use. Please eject or change the assigned drive."
+        ));
     }
     Ok(())
 }
 
-fn wait_for_helper_result(path: &Path, timeout: Duration) -> Result<HelperResult, String> {
+fn read_helper_progress(
+    path: &Path,
+    seen: &mut usize,
+    progress: &mut Option<&mut dyn FnMut(VhdMountProgress)>,
+) {
+    let Some(cb) = progress.as_mut() else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(path) else {
+        return;
+    };
+    // Only consume newline-terminated lines; the helper may be mid-write.
+    let complete = data.rfind('\n').map(|idx| &data[..idx]).unwrap_or("");
+    let lines: Vec<&str> = complete.lines().collect();
+    for line in lines.iter().skip(*seen) {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if let Ok(event) = serde_json::from_str::<VhdMountProgress>(line) {
+            cb(event);
+        }
+    }
+    *seen = (*seen).max(lines.len());
+}
+
+fn wait_for_helper_result(
+    path: &Path,
+    timeout: Duration,
+    progress_path: &Path,
+    mut progress: Option<&mut dyn FnMut(VhdMountProgress)>,
+) -> Result<HelperResult, String> {
     let start = Instant::now();
     let mut last_err: Option<String> = None;
+    let mut seen_progress = 0usize;
     while start.elapsed() < timeout {
+        read_helper_progress(progress_path, &mut seen_progress, &mut progress);
         if path.exists() {
             let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
             let trimmed = data.trim();
@@ -220,7 +438,10 @@ fn wait_for_helper_result(path: &Path, timeout: Duration) -> Result<HelperResult
             }
             let trimmed = trimmed.strip_prefix('\u{feff}').unwrap_or(trimmed);
             match serde_json::from_str::<HelperResult>(trimmed) {
-                Ok(result) => return Ok(result),
+                Ok(result) => {
+                    read_helper_progress(progress_path, &mut seen_progress, &mut progress);
+                    return Ok(result);
+                }
                 Err(err) => {
                     last_err = Some(err.to_string());
                 }
@@ -235,18 +456,43 @@ fn wait_for_helper_result(path: &Path, timeout: Duration) -> Result<HelperResult
     }
 }
 
-fn mount_vhd_via_helper(cfg: &ResolvedVhdConfig) -> Result<ElevatedVhdMount, String> {
+fn read_helper_log(path: &Path) -> Option<String> {
+    let data = fs::read_to_string(path).ok()?;
+    let trimmed = data.trim();
+    let trimmed = trimmed.strip_prefix('\u{feff}').unwrap_or(trimmed);
+    if trimmed.is_empty() {
+        return None;
+    }
+    let skip = trimmed.chars().count().saturating_sub(HELPER_LOG_TAIL_CHARS);
+    Some(trimmed.chars().skip(skip).collect())
+}
+
+fn with_helper_log(message: String, log_path: &Path) -> String {
+    match read_helper_log(log_path) {
+        Some(log) => format!("{message}\nHelper output:\n{log}"),
+        None => message,
+    }
+}
+
+fn mount_vhd_via_helper(
+    cfg: &ResolvedVhdConfig,
+    progress: Option<&mut dyn FnMut(VhdMountProgress)>,
+) -> Result<ElevatedVhdMount, String> {
     let tag = temp_tag();
     let temp = std::env::temp_dir();
     let script_path = temp.join(format!("configarc_vhd_helper_{tag}.ps1"));
     let result_path = temp.join(format!("configarc_vhd_result_{tag}.json"));
     let signal_path = temp.join(format!("configarc_vhd_signal_{tag}.flag"));
     let done_path = temp.join(format!("configarc_vhd_done_{tag}.flag"));
+    let log_path = temp.join(format!("configarc_vhd_log_{tag}.txt"));
+    let progress_path = temp.join(format!("configarc_vhd_progress_{tag}.jsonl"));
 
     fs::write(&script_path, VHD_HELPER_SCRIPT.as_bytes()).map_err(|e| e.to_string())?;
     let _ = fs::remove_file(&result_path);
     let _ = fs::remove_file(&signal_path);
     let _ = fs::remove_file(&done_path);
+    let _ = fs::remove_file(&log_path);
+    let _ = fs::remove_file(&progress_path);
 
     let args = vec![
         "-NoProfile".to_string(),
@@ -260,12 +506,20 @@ fn mount_vhd_via_helper(cfg: &ResolvedVhdConfig) -> Result<ElevatedVhdMount, Str
         cfg.patch_path.to_string_lossy().to_string(),
         "--delta".to_string(),
         if cfg.delta_enabled { "1".to_string() } else { "0".to_string() },
+        "--readonly".to_string(),
+        if cfg.read_only { "1".to_string() } else { "0".to_string() },
+        "--drive".to_string(),
+        cfg.drive_letter.to_string(),
         "--result".to_string(),
         result_path.to_string_lossy().to_string(),
         "--signal".to_string(),
         signal_path.to_string_lossy().to_string(),
         "--done".to_string(),
         done_path.to_string_lossy().to_string(),
+        "--log".to_string(),
+        log_path.to_string_lossy().to_string(),
+        "--progress".to_string(),
+        progress_path.to_string_lossy().to_string(),
     ];
 
     let arg_list = args
@@ -280,38 +534,111 @@ fn mount_vhd_via_helper(cfg: &ResolvedVhdConfig) -> Result<ElevatedVhdMount, Str
     );
     run_powershell(&cmd)?;
 
-    let result = wait_for_helper_result(&result_path, Duration::from_secs(60))?;
+    let result = wait_for_helper_result(&result_path, cfg.helper_timeout, &progress_path, progress)
+        .map_err(|err| with_helper_log(err, &log_path))?;
+    let _ = fs::remove_file(&progress_path);
     if !result.ok {
         let message = result.error.unwrap_or_else(|| "Elevated mount helper failed".to_string());
-        return Err(message);
+        return Err(with_helper_log(message, &log_path));
     }
 
-    let _ = result.mount_path;
-    let _ = result.runtime_path;
-
     Ok(ElevatedVhdMount {
         script_path,
         result_path,
         signal_path,
         done_path,
+        log_path,
+        mount_path: result.mount_path.map(PathBuf::from),
+        runtime_path: result.runtime_path.map(PathBuf::from),
+        drive_letter: cfg.drive_letter,
     })
 }
 
+fn remove_file_with_retry(path: &Path) -> Result<(), String> {
+    let mut last_err = None;
+    for _ in 0..STALE_REMOVE_ATTEMPTS {
+        match fs::remove_file(path) {
+            Ok(()) => return Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => last_err = Some(err),
+        }
+        sleep(Duration::from_millis(500));
+    }
+    Err(format!(
+        "Failed to remove {}: {}",
+        path.to_string_lossy(),
+        last_err.map(|e| e.to_string()).unwrap_or_default()
+    ))
+}
+
+/// Dismounts and deletes a runtime differencing VHD left behind by a previous session.
+pub fn cleanup_stale_runtime(cfg: &ResolvedVhdConfig) -> Result<(), String> {
+    let runtime_path = runtime_path_for_patch(&cfg.patch_path);
+    if !runtime_path.exists() {
+        return Ok(());
+    }
+    let dismount = format!(
+        "Dismount-DiskImage -ImagePath \"{}\" -Confirm:$false -ErrorAction SilentlyContinue",
+        runtime_path.to_string_lossy()
+    );
+    let _ = run_powershell(&dismount);
+    remove_file_with_retry(&runtime_path)
+        .map_err(|err| format!("Stale runtime VHD is still in use. {err}"))
+}
+
+/// Creates a differencing patch VHD whose parent is `base_path` and records both in `vhd.json`.
+pub fn create_patch_vhd(game_id: &str, base_path: &str, patch_path: &str) -> Result<VhdConfig, String> {
+    let base_dir = vhd_config_path_for_game_id(game_id)
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "Missing vhd.json parent directory".to_string())?;
+    let base = resolve_with_base(&base_dir, base_path);
+    let patch = resolve_with_base(&base_dir, patch_path);
+
+    if !base.exists() {
+        return Err(format!("Base VHD not found: {}", base.to_string_lossy()));
+    }
+    if patch.exists() {
+        return Err(format!("Patch VHD already exists: {}", patch.to_string_lossy()));
+    }
+    // Read before creating the patch, so an unreadable vhd.json fails without leaving
+    // a disk behind; only a missing one starts from the defaults.
+    let mut cfg = match load_vhd_config(game_id) {
+        Ok(cfg) => cfg,
+        Err(ConfigError::NotFound(_)) => VhdConfig::default(),
+        Err(err) => return Err(err.to_string()),
+    };
+    if let Some(parent) = patch.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let script = format!("create vdisk file=\"{}\" parent=\"{}\"\n",
+        patch.to_string_lossy(),
+        base.to_string_lossy()
+    );
+    if is_running_as_admin() {
+        run_diskpart(&script)?;
+    } else {
+        run_diskpart_elevated(&script)?;
+    }
+    if !patch.exists() {
+        return Err("Failed to create patch VHD".to_string());
+    }
+
+    cfg.base_path = base_path.to_string();
+    cfg.patch_path = patch_path.to_string();
+    save_vhd_config(game_id, &cfg).map_err(|e| e.to_string())?;
+    Ok(cfg)
+}
+
 pub fn mount_vhd(cfg: &ResolvedVhdConfig) -> Result<MountedVhd, String> {
-    ensure_x_drive_free()?;
+    ensure_drive_free(cfg.drive_letter)?;
 
     let mut mount_path = cfg.patch_path.clone();
     let mut runtime_path = None;
     if cfg.delta_enabled {
         let delta_path = runtime_path_for_patch(&cfg.patch_path);
-        let dismount = format!(
-            "Dismount-DiskImage -ImagePath \"{}\" -Confirm:$false -ErrorAction SilentlyContinue",
-            delta_path.to_string_lossy()
-        );
-        let _ = run_powershell(&dismount);
-        if delta_path.exists() {
-            let _ = fs::remove_file(&delta_path);
-        }
+        cleanup_stale_runtime(cfg)?;
         let script = format!("create vdisk file=\"{}\" parent=\"{}\"\n",
             delta_path.to_string_lossy(),
             cfg.patch_path.to_string_lossy()
@@ -324,9 +651,24 @@ pub fn mount_vhd(cfg: &ResolvedVhdConfig) -> Result<MountedVhd, String> {
         runtime_path = Some(delta_path);
     }
 
+    // Read-only mounts never create a delta, so they can attach the patch disk directly.
+    #[cfg(all(target_os = "windows", feature = "virtdisk"))]
+    {
+        if cfg.read_only && crate::virtdisk::attach_read_only(&mount_path, cfg.drive_letter).is_ok() {
+            return Ok(MountedVhd {
+                mount_path,
+                runtime_path,
+                drive_letter: cfg.drive_letter,
+            });
+        }
+    }
+
+    let access = if cfg.read_only { "ReadOnly" } else { "ReadWrite" };
     let mount_cmd = format!(
-        "Mount-DiskImage -ImagePath \"{}\" -StorageType VHD -NoDriveLetter -Passthru -Access ReadWrite -Confirm:$false -ErrorAction Stop | Get-Disk | Get-Partition | Where-Object {{ ($_ | Get-Volume) -ne $Null }} | Add-PartitionAccessPath -AccessPath \"X:\\\" -ErrorAction Stop | Out-Null",
-        mount_path.to_string_lossy()
+        "Mount-DiskImage -ImagePath \"{}\" -StorageType VHD -NoDriveLetter -Passthru -Access {} -Confirm:$false -ErrorAction Stop | Get-Disk | Get-Partition | Where-Object {{ ($_ | Get-Volume) -ne $Null }} | Add-PartitionAccessPath -AccessPath \"{}\" -ErrorAction Stop | Out-Null",
+        mount_path.to_string_lossy(),
+        access,
+        drive_root(cfg.drive_letter)
     );
     if let Err(err) = run_powershell(&mount_cmd) {
         if let Some(runtime_path) = &runtime_path {
@@ -342,15 +684,24 @@ pub fn mount_vhd(cfg: &ResolvedVhdConfig) -> Result<MountedVhd, String> {
         return Err(err);
     }
 
-    close_explorer_for_x_drive();
+    close_explorer_for_drive(cfg.drive_letter);
 
     Ok(MountedVhd {
         mount_path,
         runtime_path,
+        drive_letter: cfg.drive_letter,
     })
 }
 
 pub fn unmount_vhd(mounted: &MountedVhd) -> Result<(), String> {
+    #[cfg(all(target_os = "windows", feature = "virtdisk"))]
+    {
+        if mounted.runtime_path.is_none() && crate::virtdisk::detach(&mounted.mount_path).is_ok() {
+            return Ok(());
+        }
+    }
+
+    close_explorer_for_drive(mounted.drive_letter);
     let dismount = format!(
         "Dismount-DiskImage -ImagePath \"{}\" -Confirm:$false -ErrorAction SilentlyContinue",
         mounted.mount_path.to_string_lossy()
@@ -370,11 +721,28 @@ pub fn unmount_vhd(mounted: &MountedVhd) -> Result<(), String> {
     Ok(())
 }
 
-pub fn mount_vhd_with_elevation(cfg: &ResolvedVhdConfig) -> Result<VhdMountHandle, String> {
+/// Mounts in-process (through `virtdisk` when enabled) if IRIS is elevated, and through
+/// the elevated PowerShell helper otherwise; see the `virtdisk` module for why the helper
+/// keeps using `Mount-DiskImage`.
+pub fn mount_vhd_with_elevation(
+    cfg: &ResolvedVhdConfig,
+    mut progress: Option<&mut dyn FnMut(VhdMountProgress)>,
+) -> Result<VhdMountHandle, String> {
     if is_running_as_admin() {
-        mount_vhd(cfg).map(VhdMountHandle::Direct)
+        let mut report = |stage: &str| {
+            if let Some(cb) = progress.as_mut() {
+                cb(VhdMountProgress {
+                    stage: stage.to_string(),
+                    message: Some(drive_root(cfg.drive_letter)),
+                });
+            }
+        };
+        report("mount");
+        let mounted = mount_vhd(cfg)?;
+        report("ready");
+        Ok(VhdMountHandle::Direct(mounted))
     } else {
-        mount_vhd_via_helper(cfg).map(VhdMountHandle::Elevated)
+        mount_vhd_via_helper(cfg, progress).map(VhdMountHandle::Elevated)
     }
 }
 
@@ -398,6 +766,7 @@ pub fn unmount_vhd_handle(handle: &VhdMountHandle) -> Result<(), String> {
                 let _ = fs::remove_file(&mounted.result_path);
                 let _ = fs::remove_file(&mounted.done_path);
                 let _ = fs::remove_file(&mounted.script_path);
+                let _ = fs::remove_file(&mounted.log_path);
                 Ok(())
             } else {
                 Err("Timed out waiting for elevated unmount".to_string())
@@ -0,0 +1,350 @@
+//! Read-only VHD attach through the Windows virtual disk API (`virtdisk.dll`).
+//!
+//! `Mount-DiskImage` starts a PowerShell process per call and its pipeline depends on
+//! the storage cmdlets behaving the same on every locale. Attaching with
+//! `AttachVirtualDisk` and assigning the drive letter with `SetVolumeMountPointW` stays
+//! in-process. Differencing disks are still created with diskpart, and callers fall back
+//! to the PowerShell path whenever this module returns an error.
+//!
+//! Attaching needs administrator rights, so only an elevated IRIS takes this path. When
+//! IRIS runs unelevated the mount goes through the elevated PowerShell helper, which has
+//! no way to reach this code: calling `virtdisk.dll` from the script would mean compiling
+//! a P/Invoke shim with `Add-Type` on every mount.
+//!
+//! Latency has not been measured. This path was written without access to a Windows
+//! machine, so there are no attach or detach timings against `Mount-DiskImage` and
+//! `Dismount-DiskImage` yet, and no speedup should be assumed. To measure it, time
+//! `mount_vhd` and `unmount_vhd` for the same image several times with this path and
+//! with the PowerShell fallback, and record the results here.
+
+use std::ffi::{c_void, OsStr};
+use std::iter::once;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+type Handle = *mut c_void;
+
+const INVALID_HANDLE_VALUE: Handle = -1isize as Handle;
+const ERROR_SUCCESS: u32 = 0;
+const MAX_PATH: usize = 260;
+
+const VIRTUAL_STORAGE_TYPE_DEVICE_UNKNOWN: u32 = 0;
+const VIRTUAL_DISK_ACCESS_ATTACH_RO: u32 = 0x0001_0000;
+const VIRTUAL_DISK_ACCESS_DETACH: u32 = 0x0004_0000;
+const VIRTUAL_DISK_ACCESS_GET_INFO: u32 = 0x0008_0000;
+const OPEN_VIRTUAL_DISK_FLAG_NONE: u32 = 0;
+const ATTACH_VIRTUAL_DISK_VERSION_1: u32 = 1;
+const ATTACH_VIRTUAL_DISK_FLAG_READ_ONLY: u32 = 0x1;
+const ATTACH_VIRTUAL_DISK_FLAG_NO_DRIVE_LETTER: u32 = 0x2;
+const ATTACH_VIRTUAL_DISK_FLAG_PERMANENT_LIFETIME: u32 = 0x4;
+const DETACH_VIRTUAL_DISK_FLAG_NONE: u32 = 0;
+
+const FILE_SHARE_READ: u32 = 0x1;
+const FILE_SHARE_WRITE: u32 = 0x2;
+const OPEN_EXISTING: u32 = 3;
+const IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS: u32 = 0x0056_0000;
+
+const VOLUME_ARRIVAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[repr(C)]
+struct Guid {
+    data1: u32,
+    data2: u16,
+    data3: u16,
+    data4: [u8; 8],
+}
+
+#[repr(C)]
+struct VirtualStorageType {
+    device_id: u32,
+    vendor_id: Guid,
+}
+
+#[repr(C)]
+struct AttachVirtualDiskParameters {
+    version: u32,
+    reserved: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct DiskExtent {
+    disk_number: u32,
+    starting_offset: i64,
+    extent_length: i64,
+}
+
+#[repr(C)]
+struct VolumeDiskExtents {
+    number_of_disk_extents: u32,
+    extents: [DiskExtent; 4],
+}
+
+#[link(name = "virtdisk")]
+extern "system" {
+    fn OpenVirtualDisk(
+        virtual_storage_type: *const VirtualStorageType,
+        path: *const u16,
+        virtual_disk_access_mask: u32,
+        flags: u32,
+        parameters: *const c_void,
+        handle: *mut Handle,
+    ) -> u32;
+    fn AttachVirtualDisk(
+        virtual_disk_handle: Handle,
+        security_descriptor: *const c_void,
+        flags: u32,
+        provider_specific_flags: u32,
+        parameters: *const AttachVirtualDiskParameters,
+        overlapped: *mut c_void,
+    ) -> u32;
+    fn DetachVirtualDisk(virtual_disk_handle: Handle, flags: u32, provider_specific_flags: u32) -> u32;
+    fn GetVirtualDiskPhysicalPath(
+        virtual_disk_handle: Handle,
+        disk_path_size_in_bytes: *mut u32,
+        disk_path: *mut u16,
+    ) -> u32;
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn CloseHandle(handle: Handle) -> i32;
+    fn CreateFileW(
+        file_name: *const u16,
+        desired_access: u32,
+        share_mode: u32,
+        security_attributes: *const c_void,
+        creation_disposition: u32,
+        flags_and_attributes: u32,
+        template_file: Handle,
+    ) -> Handle;
+    fn DeviceIoControl(
+        device: Handle,
+        io_control_code: u32,
+        in_buffer: *const c_void,
+        in_buffer_size: u32,
+        out_buffer: *mut c_void,
+        out_buffer_size: u32,
+        bytes_returned: *mut u32,
+        overlapped: *mut c_void,
+    ) -> i32;
+    fn FindFirstVolumeW(volume_name: *mut u16, buffer_length: u32) -> Handle;
+    fn FindNextVolumeW(find_volume: Handle, volume_name: *mut u16, buffer_length: u32) -> i32;
+    fn FindVolumeClose(find_volume: Handle) -> i32;
+    fn SetVolumeMountPointW(volume_mount_point: *const u16, volume_name: *const u16) -> i32;
+}
+
+struct OwnedHandle(Handle);
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+fn wide(value: &OsStr) -> Vec<u16> {
+    value.encode_wide().chain(once(0)).collect()
+}
+
+fn win_error(call: &str, code: u32) -> String {
+    format!("{call} failed: {}", std::io::Error::from_raw_os_error(code as i32))
+}
+
+fn last_error(call: &str) -> String {
+    format!("{call} failed: {}", std::io::Error::last_os_error())
+}
+
+fn open_virtual_disk(path: &Path, access_mask: u32) -> Result<OwnedHandle, String> {
+    let storage_type = VirtualStorageType {
+        device_id: VIRTUAL_STORAGE_TYPE_DEVICE_UNKNOWN,
+        vendor_id: Guid {
+            data1: 0,
+            data2: 0,
+            data3: 0,
+            data4: [0; 8],
+        },
+    };
+    let path = wide(path.as_os_str());
+    let mut handle: Handle = ptr::null_mut();
+    let status = unsafe {
+        OpenVirtualDisk(
+            &storage_type,
+            path.as_ptr(),
+            access_mask,
+            OPEN_VIRTUAL_DISK_FLAG_NONE,
+            ptr::null(),
+            &mut handle,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return Err(win_error("OpenVirtualDisk", status));
+    }
+    Ok(OwnedHandle(handle))
+}
+
+fn physical_disk_number(disk: &OwnedHandle) -> Result<u32, String> {
+    let mut buffer = [0u16; MAX_PATH];
+    let mut size = (buffer.len() * 2) as u32;
+    let status = unsafe { GetVirtualDiskPhysicalPath(disk.0, &mut size, buffer.as_mut_ptr()) };
+    if status != ERROR_SUCCESS {
+        return Err(win_error("GetVirtualDiskPhysicalPath", status));
+    }
+    let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+    let path = String::from_utf16_lossy(&buffer[..len]);
+    path.rsplit("PhysicalDrive")
+        .next()
+        .and_then(|number| number.parse().ok())
+        .ok_or_else(|| format!("Unexpected physical disk path: {path}"))
+}
+
+fn volume_on_disk(volume_name: &[u16], disk_number: u32) -> bool {
+    // CreateFileW wants the volume GUID path without its trailing backslash.
+    let mut device: Vec<u16> = volume_name
+        .iter()
+        .copied()
+        .take_while(|c| *c != 0)
+        .collect();
+    if device.last() == Some(&(b'\\' as u16)) {
+        device.pop();
+    }
+    device.push(0);
+    let handle = unsafe {
+        CreateFileW(
+            device.as_ptr(),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            ptr::null(),
+            OPEN_EXISTING,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return false;
+    }
+    let volume = OwnedHandle(handle);
+    let mut extents = VolumeDiskExtents {
+        number_of_disk_extents: 0,
+        extents: [DiskExtent {
+            disk_number: 0,
+            starting_offset: 0,
+            extent_length: 0,
+        }; 4],
+    };
+    let mut returned = 0u32;
+    let ok = unsafe {
+        DeviceIoControl(
+            volume.0,
+            IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS,
+            ptr::null(),
+            0,
+            &mut extents as *mut VolumeDiskExtents as *mut c_void,
+            std::mem::size_of::<VolumeDiskExtents>() as u32,
+            &mut returned,
+            ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return false;
+    }
+    let count = (extents.number_of_disk_extents as usize).min(extents.extents.len());
+    extents.extents[..count]
+        .iter()
+        .any(|extent| extent.disk_number == disk_number)
+}
+
+fn find_volume_for_disk(disk_number: u32) -> Option<Vec<u16>> {
+    let mut name = [0u16; MAX_PATH];
+    let find = unsafe { FindFirstVolumeW(name.as_mut_ptr(), name.len() as u32) };
+    if find == INVALID_HANDLE_VALUE {
+        return None;
+    }
+    let mut found = None;
+    loop {
+        if volume_on_disk(&name, disk_number) {
+            found = Some(name.to_vec());
+            break;
+        }
+        if unsafe { FindNextVolumeW(find, name.as_mut_ptr(), name.len() as u32) } == 0 {
+            break;
+        }
+    }
+    unsafe {
+        FindVolumeClose(find);
+    }
+    found
+}
+
+fn wait_for_volume(disk_number: u32) -> Result<Vec<u16>, String> {
+    let start = Instant::now();
+    loop {
+        if let Some(volume) = find_volume_for_disk(disk_number) {
+            return Ok(volume);
+        }
+        if start.elapsed() >= VOLUME_ARRIVAL_TIMEOUT {
+            return Err(format!("No volume appeared on PhysicalDrive{disk_number}"));
+        }
+        sleep(Duration::from_millis(200));
+    }
+}
+
+fn assign_drive_letter(volume_name: &[u16], drive_letter: char) -> Result<(), String> {
+    let mount_point = wide(OsStr::new(&format!("{drive_letter}:\\")));
+    let ok = unsafe { SetVolumeMountPointW(mount_point.as_ptr(), volume_name.as_ptr()) };
+    if ok == 0 {
+        return Err(last_error("SetVolumeMountPointW"));
+    }
+    Ok(())
+}
+
+/// Attaches `path` read-only and exposes its first volume as `drive_letter`.
+/// The attach outlives this process; release it with [`detach`].
+pub fn attach_read_only(path: &Path, drive_letter: char) -> Result<(), String> {
+    let disk = open_virtual_disk(
+        path,
+        VIRTUAL_DISK_ACCESS_ATTACH_RO | VIRTUAL_DISK_ACCESS_DETACH | VIRTUAL_DISK_ACCESS_GET_INFO,
+    )?;
+    let parameters = AttachVirtualDiskParameters {
+        version: ATTACH_VIRTUAL_DISK_VERSION_1,
+        reserved: 0,
+    };
+    let status = unsafe {
+        AttachVirtualDisk(
+            disk.0,
+            ptr::null(),
+            ATTACH_VIRTUAL_DISK_FLAG_READ_ONLY
+                | ATTACH_VIRTUAL_DISK_FLAG_NO_DRIVE_LETTER
+                | ATTACH_VIRTUAL_DISK_FLAG_PERMANENT_LIFETIME,
+            0,
+            &parameters,
+            ptr::null_mut(),
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return Err(win_error("AttachVirtualDisk", status));
+    }
+
+    let result = physical_disk_number(&disk)
+        .and_then(wait_for_volume)
+        .and_then(|volume| assign_drive_letter(&volume, drive_letter));
+    if result.is_err() {
+        unsafe {
+            DetachVirtualDisk(disk.0, DETACH_VIRTUAL_DISK_FLAG_NONE, 0);
+        }
+    }
+    result
+}
+
+pub fn detach(path: &Path) -> Result<(), String> {
+    let disk = open_virtual_disk(path, VIRTUAL_DISK_ACCESS_DETACH)?;
+    let status = unsafe { DetachVirtualDisk(disk.0, DETACH_VIRTUAL_DISK_FLAG_NONE, 0) };
+    if status != ERROR_SUCCESS {
+        return Err(win_error("DetachVirtualDisk", status));
+    }
+    Ok(())
+}
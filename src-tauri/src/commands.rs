@@ -1,18 +1,61 @@
-﻿use crate::config::paths::{get_active_game_id, segatoools_path_for_game_id, set_active_game_id};
-use crate::config::{default_segatoools_config, load_segatoools_config, save_segatoools_config, SegatoolsConfig};
-use crate::games::{launcher::launch_game_child, model::{Game, LaunchMode}, store};
-use crate::sync::{ConfigManager, SyncStatus};
-use crate::vhd::{load_vhd_config, mount_vhd_with_elevation, resolve_vhd_config, unmount_vhd_handle};
+﻿use crate::config::paths::{get_active_game_id, segatoools_path_for_game_id, segatools_root_for_game_id, set_active_game_id};
+use crate::config::bundle::{export_bundle, import_bundle, BundleImportResult};
+use crate::config::json_configs::{
+    patch_json_config_for_active, restore_json_config_for_active, save_json_config_for_active, validate_json_config,
+    JsonFieldError, JsonPatchResult,
+};
+use crate::config::network_profiles::{apply_network_profile, list_network_profiles, save_network_profile, NetworkProfile};
+use crate::config::segatools::{generate_aime_access_code, serial_problems, validate_aime_access_code};
+use crate::config::{
+    default_segatoools_config, inspect_segatoools_ini, load_segatoools_config, reset_section, save_segatoools_config,
+    segatoools_edited_externally, segatoools_save_would_change, set_section_enabled, SegatoolsConfig, SegatoolsIniInspection,
+};
+use crate::error::ConfigError;
+use crate::games::{
+    launcher::{
+        device_files, ensure_device_dirs, launch_game_child, launch_game_debug, missing_launch_files, resolve_game_path,
+        run_post_exit_hook, run_pre_launch_hook, stop_game, wait_for_game_exit, DeviceFile,
+    },
+    model::{Game, LaunchMode},
+    store,
+};
+use crate::http::{build_client, HttpOptions};
+use crate::sync::{remote_config_sections, validate_remote_config, ConfigManager, EffectiveConfigWithSource, RemoteDecryptSection, SyncStatus};
+use crate::trusted::{
+    backups_for_active, deploy_segatoools_for_active, sha256_hex, verify_segatoools_for_active, BackupMetadata, DeployProgress, DeployResult,
+    SegatoolsTrustStatus,
+};
+use crate::vhd::{
+    cleanup_stale_runtime, create_patch_vhd, drive_letters, drive_root, ensure_drive_free, load_vhd_config, mount_vhd_with_elevation,
+    resolve_vhd_config, save_vhd_config, unmount_vhd_handle, wait_for_drive, DriveLetterStatus, VhdConfig, VhdMountHandle,
+    VhdMountProgress,
+};
 use crate::IrisState;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
 use std::sync::{atomic::Ordering, Arc};
-use std::time::Duration;
-use tauri::{command, AppHandle, State};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter, State};
 
-#[derive(Serialize)]
+/// How long a startup flow run's sync and decrypt results stay reusable.
+const STARTUP_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Sync status and decrypt outcome of the last startup flow run, so the rerun after
+/// `confirm_launch_cmd` does not sync and decrypt again. Only a confirmed run takes it;
+/// it is dropped once the effective config no longer matches `config` or the TTL runs out.
+pub struct StartupCache {
+    at: Instant,
+    config: Value,
+    sync_status: SyncStatus,
+    decrypt: Option<DecryptOutcome>,
+}
+
+#[derive(Clone, Serialize)]
 pub struct StartupStep {
+    /// Stable identifier for the step, e.g. `auth_check` or `mount_vhd`; `name` is only
+    /// display text.
+    pub key: String,
     pub name: String,
     pub status: String,
     pub detail: Option<String>,
@@ -25,6 +68,69 @@ pub struct StartupResult {
     pub can_launch: bool,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearRemoteCacheResult {
+    pub existed: bool,
+    pub sync: Option<SyncStatus>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveGameCheckedResult {
+    pub saved: bool,
+    pub duplicate_of: Option<String>,
+    /// Path problems from `store::check_game_paths`; they do not stop the save.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportGamesResult {
+    pub imported: usize,
+    /// Subdirectories with no supported game, or whose game is already in the list.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheckResult {
+    pub endpoint: Option<String>,
+    pub reachable: bool,
+    pub current_version: String,
+    pub latest_version: Option<String>,
+    pub url: Option<String>,
+    pub update_available: bool,
+    pub error: Option<String>,
+}
+
+/// Version JSON an update endpoint may answer with.
+#[derive(Deserialize)]
+struct UpdateManifest {
+    version: String,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameReadiness {
+    pub game_id: String,
+    pub checks: Vec<StartupStep>,
+    /// False when any check reports `"error"`.
+    pub ready: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VhdMountStatus {
+    pub mounted: bool,
+    pub mount_path: Option<String>,
+    pub runtime_path: Option<String>,
+    pub drive_path: Option<String>,
+    pub elevated: bool,
+}
+
 #[command]
 pub fn get_local_override_cmd(app: AppHandle) -> Result<Value, String> {
     let manager = ConfigManager::new(&app)?;
@@ -43,12 +149,66 @@ pub fn get_effective_config_cmd(app: AppHandle) -> Result<Value, String> {
     Ok(manager.effective_config())
 }
 
+#[command]
+pub fn get_effective_config_sources_cmd(app: AppHandle) -> Result<EffectiveConfigWithSource, String> {
+    let manager = ConfigManager::new(&app)?;
+    Ok(manager.effective_config_with_source())
+}
+
 #[command]
 pub fn sync_remote_config_cmd(app: AppHandle, endpoint: Option<String>) -> Result<SyncStatus, String> {
     let manager = ConfigManager::new(&app)?;
     Ok(manager.sync_remote(endpoint))
 }
 
+#[command]
+pub fn clear_remote_cache_cmd(app: AppHandle, resync: Option<bool>) -> Result<ClearRemoteCacheResult, String> {
+    let manager = ConfigManager::new(&app)?;
+    let existed = manager.clear_remote_cache()?;
+    let sync = if resync.unwrap_or(false) {
+        Some(manager.sync_remote(None))
+    } else {
+        None
+    };
+    Ok(ClearRemoteCacheResult { existed, sync })
+}
+
+/// Queries `/updates/endpoint` from the effective config. `update_available` is only set
+/// when the endpoint answers with `{ "version": ..., "url": ... }` newer than this app.
+#[command]
+pub fn check_updates_cmd(app: AppHandle) -> Result<UpdateCheckResult, String> {
+    let manager = ConfigManager::new(&app)?;
+    let config = remote_config_sections(&manager.effective_config());
+    let endpoint = config
+        .updates
+        .and_then(|updates| updates.endpoint)
+        .filter(|endpoint| !endpoint.trim().is_empty());
+    let mut result = UpdateCheckResult {
+        endpoint: endpoint.clone(),
+        reachable: false,
+        current_version: env!("CARGO_PKG_VERSION").to_string(),
+        latest_version: None,
+        url: None,
+        update_available: false,
+        error: None,
+    };
+    let Some(endpoint) = endpoint else {
+        return Ok(result);
+    };
+    match check_update_endpoint(&endpoint) {
+        Ok(manifest) => {
+            result.reachable = true;
+            if let Some(manifest) = manifest {
+                result.update_available = version_is_newer(&manifest.version, &result.current_version);
+                result.latest_version = Some(manifest.version);
+                result.url = manifest.url;
+            }
+        }
+        Err(err) => result.error = Some(err),
+    }
+    Ok(result)
+}
+
 #[command]
 pub fn apply_games_from_config_cmd(app: AppHandle) -> Result<usize, String> {
     let manager = ConfigManager::new(&app)?;
@@ -72,11 +232,56 @@ pub fn list_games_cmd() -> Result<Vec<Game>, String> {
     store::list_games().map_err(|e| e.to_string())
 }
 
+/// Copies game `id` and its segatools directory under a new id, e.g. for a second server.
+#[command]
+pub fn duplicate_game_cmd(id: String, new_name: String) -> Result<Game, String> {
+    store::duplicate_game(&id, &new_name).map_err(|e| e.to_string())
+}
+
+/// Re-ids a game, moving its segatools directory and following it in the active game.
+#[command]
+pub fn rename_game_id_cmd(old_id: String, new_id: String) -> Result<(), String> {
+    store::rename_game_id(&old_id, &new_id).map_err(|e| e.to_string())?;
+    if get_active_game_id().map_err(|e| e.to_string())?.as_deref() == Some(old_id.as_str()) {
+        set_active_game_id(new_id.trim()).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[command]
+pub fn list_games_by_tag_cmd(tag: String) -> Result<Vec<Game>, String> {
+    store::list_games_with_tag(&tag).map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn all_tags_cmd() -> Result<Vec<String>, String> {
+    store::all_tags().map_err(|e| e.to_string())
+}
+
 #[command]
 pub fn save_game_cmd(game: Game) -> Result<(), String> {
     store::save_game(game).map_err(|e| e.to_string())
 }
 
+/// Saves `game` unless another entry already uses the same executable; `force` saves anyway.
+/// `duplicate_of` names the existing entry either way, and `warnings` lists a missing
+/// executable or working directory.
+#[command]
+pub fn save_game_checked_cmd(game: Game, force: Option<bool>) -> Result<SaveGameCheckedResult, String> {
+    let duplicate_of = store::find_duplicate_game(&game).map_err(|e| e.to_string())?;
+    let warnings = store::check_game_paths(&game).iter().map(|e| e.to_string()).collect();
+    if duplicate_of.is_some() && !force.unwrap_or(false) {
+        return Ok(SaveGameCheckedResult { saved: false, duplicate_of, warnings });
+    }
+    store::save_game(game).map_err(|e| e.to_string())?;
+    Ok(SaveGameCheckedResult { saved: true, duplicate_of, warnings })
+}
+
+#[command]
+pub fn reorder_games_cmd(ids: Vec<String>) -> Result<(), String> {
+    store::reorder_games(&ids).map_err(|e| e.to_string())
+}
+
 #[command]
 pub fn delete_game_cmd(id: String) -> Result<(), String> {
     store::delete_game(&id).map_err(|e| e.to_string())
@@ -97,18 +302,72 @@ pub fn get_active_game_cmd() -> Result<Game, String> {
     active_game()
 }
 
+/// `path_override` edits an arbitrary `.ini` file, e.g. a backup, instead of the game's.
+#[command]
+pub fn load_segatools_config_cmd(game_id: Option<String>, path_override: Option<String>) -> Result<SegatoolsConfig, String> {
+    let path = resolve_segatools_path(game_id, path_override)?;
+    load_segatoools_config(&path).map_err(|e| e.to_string())
+}
+
+/// Raw segatools.ini plus the sections/keys a save keeps, for an advanced editor.
 #[command]
-pub fn load_segatools_config_cmd(game_id: Option<String>) -> Result<SegatoolsConfig, String> {
+pub fn inspect_segatools_ini_cmd(game_id: Option<String>) -> Result<SegatoolsIniInspection, String> {
     let id = resolve_game_id(game_id)?;
     let path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
-    load_segatoools_config(&path).map_err(|e| e.to_string())
+    inspect_segatoools_ini(&path).map_err(|e| e.to_string())
 }
 
+/// `prune` (default true) drops keys of managed sections that `config` does not list;
+/// pass false to keep hand-added keys. See `save_segatoools_config`. `path_override` works
+/// as in `load_segatools_config_cmd`.
 #[command]
-pub fn save_segatools_config_cmd(game_id: Option<String>, config: SegatoolsConfig) -> Result<(), String> {
+pub fn save_segatools_config_cmd(
+    game_id: Option<String>,
+    config: SegatoolsConfig,
+    prune: Option<bool>,
+    path_override: Option<String>,
+) -> Result<(), String> {
+    let path = resolve_segatools_path(game_id, path_override)?;
+    save_segatoools_config(&path, &config, prune.unwrap_or(true)).map_err(|e| e.to_string())
+}
+
+/// Whether `save_segatools_config_cmd` with the same `config` and `prune` would rewrite
+/// segatools.ini, so the UI can skip no-op saves.
+#[command]
+pub fn segatools_save_would_change_cmd(
+    game_id: Option<String>,
+    config: SegatoolsConfig,
+    prune: Option<bool>,
+) -> Result<bool, String> {
     let id = resolve_game_id(game_id)?;
     let path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
-    save_segatoools_config(&path, &config).map_err(|e| e.to_string())
+    segatoools_save_would_change(&path, &config, prune.unwrap_or(true)).map_err(|e| e.to_string())
+}
+
+/// Field problems `save_json_config_cmd` would report for `content`.
+#[command]
+pub fn validate_json_config_cmd(name: String, content: Value) -> Vec<JsonFieldError> {
+    validate_json_config(&name, &content)
+}
+
+/// Saves a `config_*.json` of the active game. Returns warnings for files of unknown kind;
+/// field errors fail the save for known kinds.
+#[command]
+pub fn save_json_config_cmd(name: String, content: Value) -> Result<Vec<JsonFieldError>, String> {
+    save_json_config_for_active(&name, &content).map_err(|e| e.to_string())
+}
+
+/// Deep-merges `patch` into a `config_*.json` of the active game. Returns the merged
+/// document with the same warnings as `save_json_config_cmd`.
+#[command]
+pub fn patch_json_config_cmd(name: String, patch: Value) -> Result<JsonPatchResult, String> {
+    patch_json_config_for_active(&name, &patch).map_err(|e| e.to_string())
+}
+
+/// Rolls a `config_*.json` of the active game back to the version its last save replaced.
+#[command]
+pub fn restore_json_config_cmd(name: String) -> Result<Value, String> {
+    restore_json_config_for_active(&name).map_err(|e| e.to_string())
 }
 
 #[command]
@@ -117,8 +376,426 @@ pub fn default_segatools_config_cmd() -> SegatoolsConfig {
 }
 
 #[command]
-pub fn scan_game_folder_cmd(path: String) -> Result<Game, String> {
-    scan_game_folder_logic(&path)
+pub fn get_vhd_config_cmd(game_id: Option<String>) -> Result<VhdConfig, String> {
+    let id = resolve_game_id(game_id)?;
+    match load_vhd_config(&id) {
+        Ok(cfg) => Ok(cfg),
+        Err(ConfigError::NotFound(_)) => Ok(VhdConfig::default()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Turns the runtime differencing disk on or off and returns `VhdConfig::validate` warnings.
+#[command]
+pub fn set_vhd_delta_cmd(game_id: Option<String>, enabled: bool) -> Result<Vec<String>, String> {
+    let id = resolve_game_id(game_id)?;
+    let mut config = match load_vhd_config(&id) {
+        Ok(cfg) => cfg,
+        Err(ConfigError::NotFound(_)) => VhdConfig::default(),
+        Err(err) => return Err(err.to_string()),
+    };
+    config.delta_enabled = enabled;
+    save_vhd_config(&id, &config).map_err(|e| e.to_string())?;
+    Ok(config.validate())
+}
+
+#[command]
+pub fn set_vhd_config_cmd(game_id: Option<String>, config: VhdConfig) -> Result<(), String> {
+    let id = resolve_game_id(game_id)?;
+    save_vhd_config(&id, &config).map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn create_patch_vhd_cmd(game_id: String, base_path: String, patch_path: String) -> Result<VhdConfig, String> {
+    create_patch_vhd(&game_id, &base_path, &patch_path)
+}
+
+#[command]
+pub fn scan_game_folder_cmd(path: String, max_depth: Option<usize>) -> Result<Game, String> {
+    scan_game_folder_logic(&path, max_depth.unwrap_or(DEFAULT_SCAN_DEPTH))
+}
+
+/// Like `scan_game_folder_cmd`, but returns every supported game found under `path`.
+#[command]
+pub fn scan_game_folder_multi_cmd(path: String, max_depth: Option<usize>) -> Result<Vec<Game>, String> {
+    scan_game_folder_multi_logic(&path, max_depth.unwrap_or(DEFAULT_SCAN_DEPTH))
+}
+
+/// Scans each immediate subdirectory of `path` (e.g. `D:\Games`) and saves every game found.
+#[command]
+pub fn import_games_from_parent_cmd(path: String, max_depth: Option<usize>) -> Result<ImportGamesResult, String> {
+    import_games_from_parent_logic(&path, max_depth.unwrap_or(DEFAULT_SCAN_DEPTH))
+}
+
+#[command]
+pub fn reset_segatools_section_cmd(game_id: Option<String>, section: String) -> Result<SegatoolsConfig, String> {
+    let id = resolve_game_id(game_id)?;
+    let path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
+    let mut config = load_segatoools_config(&path).map_err(|e| e.to_string())?;
+    reset_section(&mut config, &section).map_err(|e| e.to_string())?;
+    save_segatoools_config(&path, &config, true).map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+/// Flips the `enable` flag of one section without the frontend sending the whole config.
+#[command]
+pub fn set_section_enabled_cmd(game_id: Option<String>, section: String, enabled: bool) -> Result<SegatoolsConfig, String> {
+    let id = resolve_game_id(game_id)?;
+    let path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
+    let mut config = load_segatoools_config(&path).map_err(|e| e.to_string())?;
+    set_section_enabled(&mut config, &section, enabled).map_err(|e| e.to_string())?;
+    save_segatoools_config(&path, &config, true).map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+/// Returns a message for each keychip/pcbid serial that would fail network auth; empty when valid.
+#[command]
+pub fn validate_serials_cmd(game_id: Option<String>) -> Result<Vec<String>, String> {
+    let id = resolve_game_id(game_id)?;
+    let path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
+    let config = load_segatoools_config(&path).map_err(|e| e.to_string())?;
+    Ok(serial_problems(&config))
+}
+
+/// Checklist of the device files segatools.ini references (aime.txt, eeprom.bin, ...).
+#[command]
+pub fn list_device_files_cmd(game_id: Option<String>) -> Result<Vec<DeviceFile>, String> {
+    let id = resolve_game_id(game_id)?;
+    let game = find_game(&id)?;
+    let path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
+    let config = load_segatoools_config(&path).map_err(|e| e.to_string())?;
+    Ok(device_files(&game, &config))
+}
+
+/// Writes an aime.txt card to `aime.aimePath`, with `access_code` or a random valid code,
+/// and returns the code. An existing card is never replaced, since it holds the player's
+/// progress on the server.
+#[command]
+pub fn generate_aime_card_cmd(game_id: Option<String>, access_code: Option<String>) -> Result<String, String> {
+    let id = resolve_game_id(game_id)?;
+    let game = find_game(&id)?;
+    let ini_path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
+    let config = load_segatoools_config(&ini_path).map_err(|e| e.to_string())?;
+    if config.aime.aime_path.trim().is_empty() {
+        return Err("aime.aimePath is not set".to_string());
+    }
+
+    let code = match access_code.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        Some(code) if validate_aime_access_code(code) => code.to_string(),
+        Some(_) => return Err("Access code must be exactly 20 digits".to_string()),
+        None => generate_aime_access_code(),
+    };
+
+    let path = resolve_game_path(&game, &config.aime.aime_path);
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, &code).map_err(|e| e.to_string())?;
+    Ok(code)
+}
+
+/// Returns a warning for each risky `[clock]` setting; empty when none apply.
+#[command]
+pub fn validate_clock_cmd(game_id: Option<String>) -> Result<Vec<String>, String> {
+    let id = resolve_game_id(game_id)?;
+    let path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
+    let config = load_segatoools_config(&path).map_err(|e| e.to_string())?;
+    Ok(config.clock.validate())
+}
+
+#[command]
+pub fn list_network_profiles_cmd(game_id: Option<String>) -> Result<Vec<NetworkProfile>, String> {
+    let id = resolve_game_id(game_id)?;
+    list_network_profiles(Some(&id)).map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn save_network_profile_cmd(profile: NetworkProfile, game_id: Option<String>) -> Result<(), String> {
+    let id = resolve_game_id(game_id)?;
+    save_network_profile(&profile, Some(&id)).map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn apply_network_profile_cmd(id: String, game_id: Option<String>) -> Result<SegatoolsConfig, String> {
+    let game_id = resolve_game_id(game_id)?;
+    apply_network_profile(&id, &game_id).map_err(|e| e.to_string())
+}
+
+/// Zips the game's configuration to `output_path` (default: `configarc_bundle.zip` in the
+/// segatools root) and returns the archive path.
+#[command]
+pub fn export_bundle_cmd(game_id: Option<String>, output_path: Option<String>) -> Result<String, String> {
+    let id = resolve_game_id(game_id)?;
+    let dest = output_path
+        .filter(|p| !p.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| segatools_root_for_game_id(&id).join("configarc_bundle.zip"));
+    export_bundle(&id, &dest).map_err(|e| e.to_string())?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+#[command]
+pub fn import_bundle_cmd(game_id: Option<String>, path: String) -> Result<BundleImportResult, String> {
+    let id = resolve_game_id(game_id)?;
+    import_bundle(&id, Path::new(&path)).map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn verify_segatools_trust_cmd(force: Option<bool>) -> Result<SegatoolsTrustStatus, String> {
+    verify_segatoools_for_active(force.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// Deploys the trusted segatools artifact for the active game, emitting `deploy-progress`
+/// events while downloading and extracting. Async so the events reach the UI live.
+#[command(async)]
+pub fn deploy_segatools_cmd(app: AppHandle, force: Option<bool>, incremental: Option<bool>) -> Result<DeployResult, String> {
+    let mut emit_progress = |progress: DeployProgress| {
+        let _ = app.emit("deploy-progress", progress);
+    };
+    deploy_segatoools_for_active(force.unwrap_or(false), incremental.unwrap_or(false), Some(&mut emit_progress))
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn list_backup_cmd() -> Result<Vec<BackupMetadata>, String> {
+    backups_for_active().map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn generate_keys_template_cmd(game_ids: Vec<String>) -> Value {
+    crate::fsdecrypt::keys_template(&game_ids)
+}
+
+#[command]
+pub fn estimate_decrypt_space_cmd(
+    files: Vec<String>,
+    output_dir: Option<String>,
+    key_url: Option<String>,
+    key_sha256: Option<String>,
+) -> Result<Vec<crate::fsdecrypt::DecryptSpaceTarget>, String> {
+    crate::fsdecrypt::estimate_decrypt_space(
+        files.into_iter().map(PathBuf::from).collect(),
+        output_dir.filter(|dir| !dir.trim().is_empty()).map(PathBuf::from),
+        key_url,
+        key_sha256,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Opens `Segatools/<id>/` in Explorer, creating it first if needed.
+#[command]
+pub fn open_segatools_dir_cmd(game_id: Option<String>) -> Result<String, String> {
+    let id = resolve_game_id(game_id)?;
+    find_game(&id)?;
+    let dir = segatools_root_for_game_id(&id);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut cmd = std::process::Command::new("explorer");
+    cmd.arg(&dir);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    cmd.spawn().map_err(|e| format!("Failed to open {}: {e}", dir.display()))?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+#[command]
+pub fn available_drive_letters_cmd() -> Vec<DriveLetterStatus> {
+    drive_letters()
+}
+
+/// Checks, without mounting, that the game's VHD config would mount: vhd.json loads, the
+/// base/patch files resolve (including the patch's parent when delta is on), settings are
+/// sane and the drive letter is free.
+#[command]
+pub fn validate_vhd_cmd(state: State<IrisState>, game_id: Option<String>) -> Result<GameReadiness, String> {
+    let id = resolve_game_id(game_id)?;
+    let mut checks = Vec::new();
+    let mut push = |key: &str, name: &str, status: &str, detail: Option<String>| {
+        checks.push(StartupStep {
+            key: key.to_string(),
+            name: name.to_string(),
+            status: status.to_string(),
+            detail,
+        });
+    };
+
+    let config = match load_vhd_config(&id) {
+        Ok(cfg) => {
+            push("vhd_config", "读取 vhd.json", "ok", None);
+            Some(cfg)
+        }
+        Err(err) => {
+            push("vhd_config", "读取 vhd.json", "error", Some(err.to_string()));
+            None
+        }
+    };
+
+    let resolved = config.as_ref().map(|cfg| resolve_vhd_config(&id, cfg));
+    match &resolved {
+        Some(Ok(_)) => push("vhd_files", "检查 VHD 文件", "ok", None),
+        Some(Err(err)) => push("vhd_files", "检查 VHD 文件", "error", Some(err.clone())),
+        None => push("vhd_files", "检查 VHD 文件", "skipped", None),
+    }
+
+    match config.as_ref().map(VhdConfig::validate) {
+        Some(warnings) if warnings.is_empty() => push("vhd_settings", "检查 VHD 设置", "ok", None),
+        Some(warnings) => push("vhd_settings", "检查 VHD 设置", "warning", Some(warnings.join("; "))),
+        None => push("vhd_settings", "检查 VHD 设置", "skipped", None),
+    }
+
+    match resolved {
+        Some(Ok(resolved)) => {
+            let mounted_here = state.mount.lock().unwrap().is_some()
+                && state.mounted_game.lock().unwrap().as_deref() == Some(id.as_str());
+            if mounted_here {
+                push("drive", "检查盘符", "ok", Some("已挂载".to_string()));
+            } else {
+                match ensure_drive_free(resolved.drive_letter) {
+                    Ok(()) => push("drive", "检查盘符", "ok", None),
+                    Err(err) => push("drive", "检查盘符", "error", Some(err)),
+                }
+            }
+        }
+        _ => push("drive", "检查盘符", "skipped", None),
+    }
+
+    let ready = checks.iter().all(|check| check.status != "error");
+    Ok(GameReadiness {
+        game_id: id,
+        checks,
+        ready,
+    })
+}
+
+/// Reports, without launching, whether the game has what a launch would need.
+#[command]
+pub fn game_readiness_cmd(app: AppHandle, game_id: Option<String>) -> Result<GameReadiness, String> {
+    let id = resolve_game_id(game_id)?;
+    let game = find_game(&id)?;
+    let mut checks = Vec::new();
+    let mut push = |key: &str, name: &str, status: &str, detail: Option<String>| {
+        checks.push(StartupStep {
+            key: key.to_string(),
+            name: name.to_string(),
+            status: status.to_string(),
+            detail,
+        });
+    };
+
+    let ini_path = segatoools_path_for_game_id(&id).map_err(|e| e.to_string())?;
+    if ini_path.exists() {
+        push("segatools_ini", "检查 segatools.ini", "ok", None);
+    } else {
+        push(
+            "segatools_ini",
+            "检查 segatools.ini",
+            "error",
+            Some("segatools.ini not found. Please deploy first.".to_string()),
+        );
+    }
+
+    match std::fs::read(&ini_path) {
+        Ok(content) => {
+            let hash = sha256_hex(&content);
+            match segatoools_edited_externally(&ini_path, &content) {
+                Some(true) => push(
+                    "ini_hash",
+                    "检查 segatools.ini 外部修改",
+                    "warning",
+                    Some(format!("segatools.ini was changed outside IRIS (sha256 {hash})")),
+                ),
+                Some(false) => push("ini_hash", "检查 segatools.ini 外部修改", "ok", Some(hash)),
+                None => push("ini_hash", "检查 segatools.ini 外部修改", "skipped", Some(hash)),
+            }
+        }
+        Err(_) => push("ini_hash", "检查 segatools.ini 外部修改", "skipped", None),
+    }
+
+    match load_segatoools_config(&ini_path) {
+        Ok(cfg) if cfg.eeprom.enable || cfg.sram.enable => {
+            let problems = ensure_device_dirs(&game, &cfg);
+            if problems.is_empty() {
+                push("device", "检查 DEVICE 目录", "ok", None);
+            } else {
+                push("device", "检查 DEVICE 目录", "warning", Some(problems.join("; ")));
+            }
+        }
+        Ok(_) => push("device", "检查 DEVICE 目录", "skipped", Some("未启用 EEPROM/SRAM".to_string())),
+        Err(_) => push("device", "检查 DEVICE 目录", "skipped", Some("无法读取 segatools.ini".to_string())),
+    }
+
+    match missing_launch_files(&game) {
+        Some(missing) => {
+            let missing_step = |files: Vec<String>| {
+                if files.is_empty() {
+                    ("ok", None)
+                } else {
+                    ("error", Some(format!("Missing {}", files.join(", "))))
+                }
+            };
+            let (status, detail) = missing_step(missing.inject);
+            push("inject", "检查注入程序", status, detail);
+            let (status, detail) = missing_step(missing.hook);
+            push("hook", "检查 Hook DLL", status, detail);
+        }
+        None => {
+            push("inject", "检查注入程序", "skipped", Some("直接启动".to_string()));
+            push("hook", "检查 Hook DLL", "skipped", Some("直接启动".to_string()));
+        }
+    }
+
+    let is_active = get_active_game_id().map_err(|e| e.to_string())?.as_deref() == Some(id.as_str());
+    if is_active {
+        match verify_segatoools_for_active(false) {
+            Ok(status) if status.trusted => push("trusted", "验证 Segatools 完整性", "ok", None),
+            Ok(status) => push("trusted", "验证 Segatools 完整性", "warning", status.reason),
+            Err(err) => push("trusted", "验证 Segatools 完整性", "warning", Some(err.to_string())),
+        }
+    } else {
+        push("trusted", "验证 Segatools 完整性", "skipped", Some("仅检查当前游戏".to_string()));
+    }
+
+    if game.launch_mode == LaunchMode::Vhd {
+        match load_vhd_config(&id)
+            .map_err(|e| e.to_string())
+            .and_then(|cfg| resolve_vhd_config(&id, &cfg))
+        {
+            Ok(_) => push("vhd_config", "检查 VHD 配置", "ok", None),
+            Err(err) => push("vhd_config", "检查 VHD 配置", "error", Some(err)),
+        }
+    } else {
+        push("vhd_config", "检查 VHD 配置", "skipped", Some("无需挂载 VHD".to_string()));
+    }
+
+    let manager = ConfigManager::new(&app)?;
+    let config = remote_config_sections(&manager.effective_config());
+    match config.vhd.as_ref().and_then(|vhd| vhd.decrypt.as_ref()) {
+        Some(decrypt) if !decrypt.files.is_empty() => {
+            match crate::fsdecrypt::load_key_status(decrypt.key_url.clone(), decrypt.key_sha256.clone()) {
+                Ok(status) => push(
+                    "keys",
+                    "检查解密密钥",
+                    "ok",
+                    Some(format!("{} ({} games)", status.key_source, status.key_game_count)),
+                ),
+                Err(err) => push("keys", "检查解密密钥", "error", Some(err.to_string())),
+            }
+        }
+        _ => push("keys", "检查解密密钥", "skipped", Some("无需解密".to_string())),
+    }
+
+    let ready = checks.iter().all(|check| check.status != "error");
+    Ok(GameReadiness {
+        game_id: id,
+        checks,
+        ready,
+    })
 }
 
 #[command]
@@ -127,12 +804,31 @@ pub fn confirm_launch_cmd(state: State<IrisState>) -> Result<(), String> {
     Ok(())
 }
 
-#[command]
+/// Runs the startup sequence, emitting each step as a `startup-step` event when it
+/// starts (`"running"`, for the slow ones) and finishes, and returns all finished steps.
+/// Runs off the main thread so the step and decrypt progress events reach the UI live.
+#[command(async)]
 pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<StartupResult, String> {
     let mut steps = Vec::new();
 
-    let push_skip = |steps: &mut Vec<StartupStep>, name: &str, detail: &str| {
-        steps.push(StartupStep {
+    let push_step = |steps: &mut Vec<StartupStep>, step: StartupStep| {
+        let _ = app.emit("startup-step", &step);
+        steps.push(step);
+    };
+    let emit_running = |key: &str, name: &str| {
+        let _ = app.emit(
+            "startup-step",
+            StartupStep {
+                key: key.to_string(),
+                name: name.to_string(),
+                status: "running".to_string(),
+                detail: None,
+            },
+        );
+    };
+    let push_skip = |steps: &mut Vec<StartupStep>, key: &str, name: &str, detail: &str| {
+        push_step(steps, StartupStep {
+            key: key.to_string(),
             name: name.to_string(),
             status: "skipped".to_string(),
             detail: Some(detail.to_string()),
@@ -140,24 +836,62 @@ pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<S
     };
 
     let manager = ConfigManager::new(&app)?;
-    let sync_status = manager.sync_remote(None);
-    let config = manager.effective_config();
+    // Taking the cache clears it, so any run other than the one resuming after
+    // `confirm_launch_cmd` syncs and decrypts from scratch.
+    let resuming = state.confirmed_launch.load(Ordering::SeqCst);
+    let cached = state.startup_cache.lock().unwrap().take().filter(|cache| {
+        resuming && cache.at.elapsed() < STARTUP_CACHE_TTL && cache.config == manager.effective_config()
+    });
+    let (sync_status, cached_at, cached_decrypt) = match cached {
+        Some(cache) => (
+            cache.sync_status,
+            cache.at,
+            cache.decrypt.filter(DecryptOutcome::outputs_exist),
+        ),
+        None => (manager.sync_remote(None), Instant::now(), None),
+    };
+    let effective = manager.effective_config();
+    let config = match validate_remote_config(&effective) {
+        Ok(config) => config,
+        Err(err) => {
+            push_step(&mut steps, StartupStep {
+                key: "auth_check".to_string(),
+                name: "验证机台授权状态".to_string(),
+                status: "error".to_string(),
+                detail: Some(err),
+            });
+            push_skip(&mut steps, "update_check", "检查机台更新", "已中断");
+            push_skip(&mut steps, "confirm_launch", "确认启动配置", "已中断");
+            push_skip(&mut steps, "decrypt", "解密游戏 VHD", "已中断");
+            push_skip(&mut steps, "mount_vhd", "挂载游戏 VHD", "已中断");
+            push_skip(&mut steps, "launch", "启动游戏", "已中断");
+            return Ok(StartupResult { steps, can_launch: false });
+        }
+    };
+    *state.startup_cache.lock().unwrap() = Some(StartupCache {
+        at: cached_at,
+        config: effective,
+        sync_status: sync_status.clone(),
+        decrypt: cached_decrypt.clone(),
+    });
 
     let authorized = config
-        .pointer("/machine/authorized")
-        .and_then(|value| value.as_bool())
+        .machine
+        .as_ref()
+        .and_then(|machine| machine.authorized)
         .unwrap_or(true);
     if !authorized {
-        steps.push(StartupStep {
+        push_step(&mut steps, StartupStep {
+            key: "auth_check".to_string(),
             name: "验证机台授权状态".to_string(),
             status: "error".to_string(),
             detail: Some("机台未授权".to_string()),
         });
-        push_skip(&mut steps, "检查机台更新", "已中断");
-        push_skip(&mut steps, "确认启动配置", "已中断");
-        push_skip(&mut steps, "解密游戏 VHD", "已中断");
-        push_skip(&mut steps, "挂载游戏 VHD", "已中断");
-        push_skip(&mut steps, "启动游戏", "已中断");
+        push_skip(&mut steps, "update_check", "检查机台更新", "已中断");
+        push_skip(&mut steps, "confirm_launch", "确认启动配置", "已中断");
+        push_skip(&mut steps, "decrypt", "解密游戏 VHD", "已中断");
+        push_skip(&mut steps, "mount_vhd", "挂载游戏 VHD", "已中断");
+        push_skip(&mut steps, "launch", "启动游戏", "已中断");
         return Ok(StartupResult { steps, can_launch: false });
     }
 
@@ -169,7 +903,8 @@ pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<S
     } else {
         None
     };
-    steps.push(StartupStep {
+    push_step(&mut steps, StartupStep {
+        key: "auth_check".to_string(),
         name: "验证机台授权状态".to_string(),
         status: if auth_detail.is_some() {
             "warning".to_string()
@@ -180,18 +915,20 @@ pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<S
     });
 
     let update_endpoint = config
-        .pointer("/updates/endpoint")
-        .and_then(|value| value.as_str())
-        .map(|value| value.to_string());
+        .updates
+        .as_ref()
+        .and_then(|updates| updates.endpoint.clone());
     if let Some(endpoint) = update_endpoint {
         let update_ok = check_update_endpoint(&endpoint).is_ok();
-        steps.push(StartupStep {
+        push_step(&mut steps, StartupStep {
+            key: "update_check".to_string(),
             name: "检查机台更新".to_string(),
             status: if update_ok { "ok".to_string() } else { "warning".to_string() },
             detail: if update_ok { None } else { Some("更新服务不可用".to_string()) },
         });
     } else {
-        steps.push(StartupStep {
+        push_step(&mut steps, StartupStep {
+            key: "update_check".to_string(),
             name: "检查机台更新".to_string(),
             status: "skipped".to_string(),
             detail: Some("未配置更新服务".to_string()),
@@ -199,41 +936,75 @@ pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<S
     }
 
     let confirm_required = config
-        .pointer("/startup/confirm_launch")
-        .and_then(|value| value.as_bool())
+        .startup
+        .as_ref()
+        .and_then(|startup| startup.confirm_launch)
         .unwrap_or(false);
-    steps.push(StartupStep {
+    if confirm_required && !state.confirmed_launch.load(Ordering::SeqCst) {
+        push_step(&mut steps, StartupStep {
+            key: "confirm_launch".to_string(),
+            name: "确认启动配置".to_string(),
+            status: "pending".to_string(),
+            detail: Some("等待确认启动配置".to_string()),
+        });
+        push_skip(&mut steps, "decrypt", "解密游戏 VHD", "等待确认");
+        push_skip(&mut steps, "mount_vhd", "挂载游戏 VHD", "等待确认");
+        push_skip(&mut steps, "launch", "启动游戏", "等待确认");
+        return Ok(StartupResult { steps, can_launch: false });
+    }
+    push_step(&mut steps, StartupStep {
+        key: "confirm_launch".to_string(),
         name: "确认启动配置".to_string(),
         status: if confirm_required { "ok".to_string() } else { "skipped".to_string() },
         detail: if confirm_required {
-            Some("自动确认".to_string())
+            Some("已确认".to_string())
         } else {
             None
         },
     });
 
-    let decrypt_outcome = decrypt_from_config(&config);
+    let decrypt_outcome = match cached_decrypt {
+        Some(outcome) => Ok(outcome),
+        None => {
+            emit_running("decrypt", "解密游戏 VHD");
+            let mut emit_progress = |progress: crate::fsdecrypt::DecryptProgress| {
+                let _ = app.emit("startup-decrypt-progress", progress);
+            };
+            decrypt_from_config(
+                config.vhd.as_ref().and_then(|vhd| vhd.decrypt.as_ref()),
+                Some(&mut emit_progress),
+            )
+        }
+    };
     let decrypt_outcome = match decrypt_outcome {
-        Ok(outcome) => outcome,
+        Ok(outcome) => {
+            if let Some(cache) = state.startup_cache.lock().unwrap().as_mut() {
+                cache.decrypt = Some(outcome.clone());
+            }
+            outcome
+        }
         Err(err) => {
-            steps.push(StartupStep {
+            push_step(&mut steps, StartupStep {
+                key: "decrypt".to_string(),
                 name: "解密游戏 VHD".to_string(),
                 status: "error".to_string(),
                 detail: Some(err),
             });
-            push_skip(&mut steps, "挂载游戏 VHD", "已中断");
-            push_skip(&mut steps, "启动游戏", "已中断");
+            push_skip(&mut steps, "mount_vhd", "挂载游戏 VHD", "已中断");
+            push_skip(&mut steps, "launch", "启动游戏", "已中断");
             return Ok(StartupResult { steps, can_launch: false });
         }
     };
 
     match decrypt_outcome {
-        DecryptOutcome::Done => steps.push(StartupStep {
+        DecryptOutcome::Done(_) => push_step(&mut steps, StartupStep {
+            key: "decrypt".to_string(),
             name: "解密游戏 VHD".to_string(),
             status: "ok".to_string(),
             detail: Some("已解密".to_string()),
         }),
-        DecryptOutcome::Skipped => steps.push(StartupStep {
+        DecryptOutcome::Skipped => push_step(&mut steps, StartupStep {
+            key: "decrypt".to_string(),
             name: "解密游戏 VHD".to_string(),
             status: "skipped".to_string(),
             detail: Some("无需解密".to_string()),
@@ -243,33 +1014,38 @@ pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<S
     let game = match active_game() {
         Ok(game) => game,
         Err(err) => {
-            steps.push(StartupStep {
+            push_step(&mut steps, StartupStep {
+                key: "load_game".to_string(),
                 name: "挂载游戏 VHD".to_string(),
                 status: "error".to_string(),
                 detail: Some(err),
             });
-            push_skip(&mut steps, "启动游戏", "已中断");
+            push_skip(&mut steps, "launch", "启动游戏", "已中断");
             return Ok(StartupResult { steps, can_launch: false });
         }
     };
 
     if game.launch_mode == LaunchMode::Vhd {
+        emit_running("mount_vhd", "挂载游戏 VHD");
         if let Err(err) = ensure_vhd_mounted(&state, &game) {
-            steps.push(StartupStep {
+            push_step(&mut steps, StartupStep {
+                key: "mount_vhd".to_string(),
                 name: "挂载游戏 VHD".to_string(),
                 status: "error".to_string(),
                 detail: Some(err),
             });
-            push_skip(&mut steps, "启动游戏", "已中断");
+            push_skip(&mut steps, "launch", "启动游戏", "已中断");
             return Ok(StartupResult { steps, can_launch: false });
         }
-        steps.push(StartupStep {
+        push_step(&mut steps, StartupStep {
+            key: "mount_vhd".to_string(),
             name: "挂载游戏 VHD".to_string(),
             status: "ok".to_string(),
             detail: Some("已挂载".to_string()),
         });
     } else {
-        steps.push(StartupStep {
+        push_step(&mut steps, StartupStep {
+            key: "mount_vhd".to_string(),
             name: "挂载游戏 VHD".to_string(),
             status: "skipped".to_string(),
             detail: Some("无需挂载 VHD".to_string()),
@@ -278,14 +1054,18 @@ pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<S
 
     let launch_result = launch_game_internal(&state, &game);
     if let Err(err) = launch_result {
-        steps.push(StartupStep {
+        push_step(&mut steps, StartupStep {
+            key: "launch".to_string(),
             name: "启动游戏".to_string(),
             status: "error".to_string(),
             detail: Some(err),
         });
         return Ok(StartupResult { steps, can_launch: false });
     }
-    steps.push(StartupStep {
+    // A confirmation covers one launch.
+    state.confirmed_launch.store(false, Ordering::SeqCst);
+    push_step(&mut steps, StartupStep {
+        key: "launch".to_string(),
         name: "启动游戏".to_string(),
         status: "ok".to_string(),
         detail: None,
@@ -294,6 +1074,59 @@ pub fn run_startup_flow_cmd(app: AppHandle, state: State<IrisState>) -> Result<S
     Ok(StartupResult { steps, can_launch: true })
 }
 
+/// Mounts the game's VHD, emitting `vhd-mount-progress` events. Async so a slow or
+/// elevated mount does not block the window.
+#[command(async)]
+pub fn mount_vhd_cmd(app: AppHandle, state: State<IrisState>, game_id: Option<String>) -> Result<(), String> {
+    let id = resolve_game_id(game_id)?;
+    let mut emit_progress = |progress: VhdMountProgress| {
+        let _ = app.emit("vhd-mount-progress", progress);
+    };
+    mount_game_vhd(&state, &id, Some(&mut emit_progress))
+}
+
+#[command]
+pub fn vhd_mount_status_cmd(state: State<IrisState>) -> VhdMountStatus {
+    let handle = state.mount.lock().unwrap().clone();
+    let path_string = |path: &Path| path.to_string_lossy().to_string();
+    match handle {
+        Some(VhdMountHandle::Direct(mounted)) => VhdMountStatus {
+            mounted: true,
+            mount_path: Some(path_string(&mounted.mount_path)),
+            runtime_path: mounted.runtime_path.as_deref().map(path_string),
+            drive_path: Some(drive_root(mounted.drive_letter)),
+            elevated: false,
+        },
+        Some(VhdMountHandle::Elevated(mounted)) => VhdMountStatus {
+            mounted: true,
+            mount_path: mounted.mount_path.as_deref().map(path_string),
+            runtime_path: mounted.runtime_path.as_deref().map(path_string),
+            drive_path: Some(drive_root(mounted.drive_letter)),
+            elevated: true,
+        },
+        None => VhdMountStatus {
+            mounted: false,
+            mount_path: None,
+            runtime_path: None,
+            drive_path: None,
+            elevated: false,
+        },
+    }
+}
+
+#[command]
+pub fn unmount_vhd_cmd(state: State<IrisState>) -> Result<(), String> {
+    let handle = state.mount.lock().unwrap().take();
+    let Some(handle) = handle else {
+        return Ok(());
+    };
+    if let Err(err) = unmount_vhd_handle(&handle) {
+        *state.mount.lock().unwrap() = Some(handle);
+        return Err(err);
+    }
+    Ok(())
+}
+
 #[command]
 pub fn launch_active_game_cmd(state: State<IrisState>) -> Result<(), String> {
     let game = active_game()?;
@@ -301,6 +1134,39 @@ pub fn launch_active_game_cmd(state: State<IrisState>) -> Result<(), String> {
     launch_game_internal(&state, &game)
 }
 
+/// Force-stops the game started by `launch_active_game_cmd` or the startup flow, together
+/// with `amdaemon.exe`, then unmounts the VHD mounted for it. That is the game that was
+/// launched, even if the active game changed since; the active game only when none runs.
+#[command]
+pub fn stop_active_game_cmd(state: State<IrisState>) -> Result<(), String> {
+    let running = state.running_game.lock().unwrap().take();
+    let (game, pid) = match running {
+        Some((id, pid)) => (find_game(&id)?, Some(pid)),
+        None => (active_game()?, None),
+    };
+    stop_game(&game, pid).map_err(|e| e.to_string())?;
+    unmount_vhd_cmd(state)
+}
+
+/// Launches a game and waits for it, returning the captured batch output. The VHD
+/// mounted for the run is released once the process exits. Async because it waits out
+/// the whole play session.
+#[command(async)]
+pub fn launch_game_debug_cmd(state: State<IrisState>, game_id: Option<String>) -> Result<String, String> {
+    let id = resolve_game_id(game_id)?;
+    let game = find_game(&id)?;
+    let already_mounted = state.mount.lock().unwrap().is_some();
+    ensure_vhd_mounted(&state, &game)?;
+    let result = launch_game_debug(&game).map_err(|e| e.to_string());
+    if !already_mounted {
+        let handle = state.mount.lock().unwrap().take();
+        if let Some(handle) = handle {
+            let _ = unmount_vhd_handle(&handle);
+        }
+    }
+    result
+}
+
 fn resolve_game_id(game_id: Option<String>) -> Result<String, String> {
     if let Some(id) = game_id {
         if !id.trim().is_empty() {
@@ -312,6 +1178,31 @@ fn resolve_game_id(game_id: Option<String>) -> Result<String, String> {
         .ok_or_else(|| "No active game selected".to_string())
 }
 
+/// `path_override` when given (it must name an `.ini` file), else the game's segatools.ini.
+fn resolve_segatools_path(game_id: Option<String>, path_override: Option<String>) -> Result<PathBuf, String> {
+    if let Some(raw) = path_override.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        let path = PathBuf::from(raw);
+        let is_ini = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("ini"))
+            .unwrap_or(false);
+        if !is_ini {
+            return Err(format!("Not an .ini file: {}", path.display()));
+        }
+        return Ok(path);
+    }
+    let id = resolve_game_id(game_id)?;
+    segatoools_path_for_game_id(&id).map_err(|e| e.to_string())
+}
+
+fn find_game(id: &str) -> Result<Game, String> {
+    let games = store::list_games().map_err(|e| e.to_string())?;
+    games
+        .into_iter()
+        .find(|game| game.id == id)
+        .ok_or_else(|| format!("Game not found: {id}"))
+}
+
 fn active_game() -> Result<Game, String> {
     let active_id = get_active_game_id()
         .map_err(|e| e.to_string())?
@@ -323,68 +1214,145 @@ fn active_game() -> Result<Game, String> {
         .ok_or_else(|| "Active game not found".to_string())
 }
 
+/// Removes a runtime VHD left behind by a crash so the next mount starts clean.
+pub fn cleanup_stale_vhd_for_active() {
+    let Ok(game) = active_game() else {
+        return;
+    };
+    if game.launch_mode != LaunchMode::Vhd {
+        return;
+    }
+    let Ok(cfg) = load_vhd_config(&game.id) else {
+        return;
+    };
+    if !cfg.delta_enabled {
+        return;
+    }
+    if let Ok(resolved) = resolve_vhd_config(&game.id, &cfg) {
+        let _ = cleanup_stale_runtime(&resolved);
+    }
+}
+
 fn ensure_vhd_mounted(state: &State<IrisState>, game: &Game) -> Result<(), String> {
     if game.launch_mode != LaunchMode::Vhd {
         return Ok(());
     }
+    mount_game_vhd(state, &game.id, None)?;
+    let letter = state.mount.lock().unwrap().as_ref().map(VhdMountHandle::drive_letter);
+    match letter {
+        Some(letter) => wait_for_drive(letter),
+        None => Ok(()),
+    }
+}
 
+fn mount_game_vhd(
+    state: &State<IrisState>,
+    game_id: &str,
+    progress: Option<&mut dyn FnMut(VhdMountProgress)>,
+) -> Result<(), String> {
     if state.mount.lock().unwrap().is_some() {
+        // The drive letter is shared, so another game's mount would break this launch.
+        let mounted_game = state.mounted_game.lock().unwrap().clone();
+        if mounted_game.is_some_and(|id| id != game_id) {
+            return Err("another game's VHD is currently mounted; stop it first".to_string());
+        }
         return Ok(());
     }
 
-    let cfg = load_vhd_config(&game.id).map_err(|e| e.to_string())?;
-    let resolved = resolve_vhd_config(&game.id, &cfg)?;
-    let handle = mount_vhd_with_elevation(&resolved)?;
+    let cfg = load_vhd_config(game_id).map_err(|e| e.to_string())?;
+    let resolved = resolve_vhd_config(game_id, &cfg)?;
+    let handle = mount_vhd_with_elevation(&resolved, progress)?;
     *state.mount.lock().unwrap() = Some(handle);
+    *state.mounted_game.lock().unwrap() = Some(game_id.to_string());
     Ok(())
 }
 
 fn launch_game_internal(state: &State<IrisState>, game: &Game) -> Result<(), String> {
-    let mount = state.mount.lock().unwrap().clone();
+    let mounted = state.mount.lock().unwrap().is_some();
+    // Order: VHD mount (by the caller), pre_launch, game, exit, post_exit, VHD unmount.
+    run_pre_launch_hook(game);
     let mut child = launch_game_child(game).map_err(|e| e.to_string())?;
-    if mount.is_some() {
-        let mount_state = Arc::clone(&state.mount);
-        std::thread::spawn(move || {
-            let _ = child.wait();
-            if let Some(handle) = mount {
-                let _ = unmount_vhd_handle(&handle);
-            }
-            if let Ok(mut guard) = mount_state.lock() {
+    let pid = child.id();
+    *state.running_game.lock().unwrap() = Some((game.id.clone(), pid));
+    let running_state = Arc::clone(&state.running_game);
+    let mount_state = Arc::clone(&state.mount);
+    let game = game.clone();
+    std::thread::spawn(move || {
+        // Waiting on `child` alone could unmount while the game still runs.
+        wait_for_game_exit(&game, &mut child);
+        if let Ok(mut guard) = running_state.lock() {
+            if guard.as_ref().is_some_and(|(_, running)| *running == pid) {
                 *guard = None;
             }
-        });
-    }
+        }
+        run_post_exit_hook(&game);
+        if !mounted {
+            return;
+        }
+        // The handle may already have been released via unmount_vhd_cmd.
+        let handle = mount_state.lock().ok().and_then(|mut guard| guard.take());
+        if let Some(handle) = handle {
+            let _ = unmount_vhd_handle(&handle);
+        }
+    });
     Ok(())
 }
 
-fn check_update_endpoint(endpoint: &str) -> Result<(), String> {
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
+/// Any response counts as reachable; the body is parsed as an `UpdateManifest` if it is one.
+fn check_update_endpoint(endpoint: &str) -> Result<Option<UpdateManifest>, String> {
+    let client = build_client(HttpOptions::quick())
         .map_err(|e| e.to_string())?;
-    client.get(endpoint).send().map_err(|e| e.to_string())?;
-    Ok(())
+    let resp = client.get(endpoint).send().map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Ok(None);
+    }
+    Ok(resp.json::<UpdateManifest>().ok())
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DecryptConfig {
-    files: Vec<String>,
-    #[serde(alias = "key_url")]
-    key_url: Option<String>,
+/// Compares dotted versions numerically (`1.10.0` > `1.9.2`), ignoring a leading `v`
+/// and any `-suffix`.
+fn version_is_newer(latest: &str, current: &str) -> bool {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .trim()
+            .trim_start_matches(['v', 'V'])
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (latest, current) = (parts(latest), parts(current));
+    let len = latest.len().max(current.len());
+    let at = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| at(&latest, i).cmp(&at(&current, i)))
+        .find(|ordering| ordering.is_ne())
+        .is_some_and(|ordering| ordering.is_gt())
 }
 
+#[derive(Clone)]
 enum DecryptOutcome {
     Skipped,
-    Done,
+    /// Decrypted; holds the output of every container.
+    Done(Vec<PathBuf>),
+}
+
+impl DecryptOutcome {
+    fn outputs_exist(&self) -> bool {
+        match self {
+            DecryptOutcome::Skipped => true,
+            DecryptOutcome::Done(outputs) => outputs.iter().all(|output| output.exists()),
+        }
+    }
 }
 
-fn decrypt_from_config(config: &Value) -> Result<DecryptOutcome, String> {
-    let decrypt_value = config.pointer("/vhd/decrypt");
-    let decrypt: Option<DecryptConfig> = decrypt_value
-        .cloned()
-        .and_then(|value| serde_json::from_value(value).ok());
-    let Some(decrypt) = decrypt else {
+fn decrypt_from_config(
+    decrypt: Option<&RemoteDecryptSection>,
+    progress: Option<&mut dyn FnMut(crate::fsdecrypt::DecryptProgress)>,
+) -> Result<DecryptOutcome, String> {
+    let Some(decrypt) = decrypt.cloned() else {
         return Ok(DecryptOutcome::Skipped);
     };
     if decrypt.files.is_empty() {
@@ -397,30 +1365,109 @@ fn decrypt_from_config(config: &Value) -> Result<DecryptOutcome, String> {
         .collect();
     let summary = crate::fsdecrypt::decrypt_game_files(
         files,
-        false,
+        crate::fsdecrypt::DecryptOptions {
+            output_dir: decrypt.output_dir.filter(|dir| !dir.trim().is_empty()).map(PathBuf::from),
+            skip_existing: decrypt.skip_existing,
+            write_manifest: decrypt.write_manifest,
+            ..Default::default()
+        },
         decrypt.key_url,
-        None,
+        decrypt.key_sha256,
+        progress,
         None,
     )
     .map_err(|e| e.to_string())?;
     if summary.results.iter().any(|result| result.failed) {
         return Err("解密失败".to_string());
     }
-    Ok(DecryptOutcome::Done)
+    Ok(DecryptOutcome::Done(
+        summary
+            .results
+            .into_iter()
+            .filter_map(|result| result.output.map(PathBuf::from))
+            .collect(),
+    ))
 }
 
-fn scan_game_folder_logic(path: &str) -> Result<Game, String> {
+fn scan_game_folder_logic(path: &str, max_depth: usize) -> Result<Game, String> {
     let dir = Path::new(path);
     if !dir.exists() || !dir.is_dir() {
         return Err("Invalid directory".to_string());
     }
 
-    let detected = detect_game_with_fallback(dir)
+    let detected = detect_game_with_fallback(dir, max_depth)
         .ok_or_else(|| "No supported game executable found".to_string())?;
 
     Ok(build_folder_game(detected))
 }
 
+fn scan_game_folder_multi_logic(path: &str, max_depth: usize) -> Result<Vec<Game>, String> {
+    let dir = Path::new(path);
+    if !dir.exists() || !dir.is_dir() {
+        return Err("Invalid directory".to_string());
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut detected = Vec::new();
+    for_each_scan_dir(dir, max_depth, |candidate| {
+        for info in detect_games_in_dir(candidate) {
+            if seen.insert(info.executable_path.to_lowercase()) {
+                detected.push(info);
+            }
+        }
+        false
+    });
+    if detected.is_empty() {
+        return Err("No supported game executable found".to_string());
+    }
+
+    // Ids are timestamps, so offset them to keep games from one scan distinct.
+    let base_id = chrono::Utc::now().timestamp_millis();
+    Ok(detected
+        .into_iter()
+        .enumerate()
+        .map(|(index, info)| {
+            let mut game = build_folder_game(info);
+            game.id = (base_id + index as i64).to_string();
+            game
+        })
+        .collect())
+}
+
+fn import_games_from_parent_logic(path: &str, max_depth: usize) -> Result<ImportGamesResult, String> {
+    let dir = Path::new(path);
+    if !dir.exists() || !dir.is_dir() {
+        return Err("Invalid directory".to_string());
+    }
+
+    let mut subdirs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    subdirs.sort();
+
+    // Ids are timestamps, so offset them to keep games from one import distinct.
+    let base_id = chrono::Utc::now().timestamp_millis();
+    let mut result = ImportGamesResult { imported: 0, skipped: Vec::new() };
+    for subdir in subdirs {
+        let Some(detected) = detect_game_with_fallback(&subdir, max_depth) else {
+            result.skipped.push(subdir.to_string_lossy().to_string());
+            continue;
+        };
+        let mut game = build_folder_game(detected);
+        game.id = (base_id + result.imported as i64).to_string();
+        if store::find_duplicate_game(&game).map_err(|e| e.to_string())?.is_some() {
+            result.skipped.push(subdir.to_string_lossy().to_string());
+            continue;
+        }
+        store::save_game(game).map_err(|e| e.to_string())?;
+        result.imported += 1;
+    }
+    Ok(result)
+}
+
 struct DetectedGameInfo {
     name: String,
     executable_path: String,
@@ -428,93 +1475,131 @@ struct DetectedGameInfo {
     launch_args: Vec<String>,
 }
 
-fn default_launch_args(game_name: &str) -> Vec<String> {
-    match game_name {
-        "Sinmai" => vec![
-            "-screen-fullscreen".into(),
-            "0".into(),
-            "-popupwindow".into(),
-            "-screen-width".into(),
-            "2160".into(),
-            "-screen-height".into(),
-            "1920".into(),
-            "-silent-crashes".into(),
-        ],
-        "Chunithm" => vec![],
-        "Ongeki" => vec![
-            "-screen-fullscreen".into(),
-            "0".into(),
-            "-popupwindow".into(),
-            "-screen-width".into(),
-            "1080".into(),
-            "-screen-height".into(),
-            "1920".into(),
-        ],
-        _ => vec![],
+const DEFAULT_SCAN_DEPTH: usize = 2;
+
+/// A supported game executable and the defaults applied when it is found by a folder scan.
+struct GameSignature {
+    exe: &'static str,
+    name: &'static str,
+    args_fn: fn() -> Vec<String>,
+}
+
+/// Checked in order; the first executable present in a directory wins.
+const GAME_SIGNATURES: &[GameSignature] = &[
+    GameSignature { exe: "Sinmai.exe", name: "Sinmai", args_fn: sinmai_launch_args },
+    GameSignature { exe: "chusanApp.exe", name: "Chunithm", args_fn: no_launch_args },
+    GameSignature { exe: "mu3.exe", name: "Ongeki", args_fn: ongeki_launch_args },
+    GameSignature { exe: "maimai_dump_.exe", name: "Maimai Finale", args_fn: no_launch_args },
+    GameSignature { exe: "chuniApp.exe", name: "Chunithm (Legacy)", args_fn: no_launch_args },
+    GameSignature { exe: "diva.exe", name: "Project DIVA Arcade", args_fn: no_launch_args },
+    GameSignature { exe: "Mercury-Win64-Shipping.exe", name: "WACCA", args_fn: no_launch_args },
+    GameSignature { exe: "InitialD0_DX11_Nu.exe", name: "Initial D Zero", args_fn: no_launch_args },
+];
+
+fn no_launch_args() -> Vec<String> {
+    vec![]
+}
+
+fn sinmai_launch_args() -> Vec<String> {
+    vec![
+        "-screen-fullscreen".into(),
+        "0".into(),
+        "-popupwindow".into(),
+        "-screen-width".into(),
+        "2160".into(),
+        "-screen-height".into(),
+        "1920".into(),
+        "-silent-crashes".into(),
+    ]
+}
+
+fn ongeki_launch_args() -> Vec<String> {
+    vec![
+        "-screen-fullscreen".into(),
+        "0".into(),
+        "-popupwindow".into(),
+        "-screen-width".into(),
+        "1080".into(),
+        "-screen-height".into(),
+        "1920".into(),
+    ]
+}
+
+fn detected_from_signature(dir: &Path, signature: &GameSignature) -> DetectedGameInfo {
+    DetectedGameInfo {
+        name: signature.name.to_string(),
+        executable_path: dir.join(signature.exe).to_string_lossy().to_string(),
+        working_dir: dir.to_string_lossy().to_string(),
+        launch_args: (signature.args_fn)(),
     }
 }
 
 fn detect_game_in_dir(dir: &Path) -> Option<DetectedGameInfo> {
-    let join_path = |p: &str| dir.join(p).to_string_lossy().to_string();
-
-    if dir.join("Sinmai.exe").exists() {
-        let name = "Sinmai".to_string();
-        return Some(DetectedGameInfo {
-            name: name.clone(),
-            executable_path: join_path("Sinmai.exe"),
-            working_dir: dir.to_string_lossy().to_string(),
-            launch_args: default_launch_args(&name),
-        });
-    }
-    if dir.join("chusanApp.exe").exists() {
-        let name = "Chunithm".to_string();
-        return Some(DetectedGameInfo {
-            name: name.clone(),
-            executable_path: join_path("chusanApp.exe"),
-            working_dir: dir.to_string_lossy().to_string(),
-            launch_args: default_launch_args(&name),
-        });
-    }
-    if dir.join("mu3.exe").exists() {
-        let name = "Ongeki".to_string();
-        return Some(DetectedGameInfo {
-            name: name.clone(),
-            executable_path: join_path("mu3.exe"),
-            working_dir: dir.to_string_lossy().to_string(),
-            launch_args: default_launch_args(&name),
-        });
-    }
-    None
+    GAME_SIGNATURES
+        .iter()
+        .find(|signature| dir.join(signature.exe).exists())
+        .map(|signature| detected_from_signature(dir, signature))
+}
+
+fn detect_games_in_dir(dir: &Path) -> Vec<DetectedGameInfo> {
+    GAME_SIGNATURES
+        .iter()
+        .filter(|signature| dir.join(signature.exe).exists())
+        .map(|signature| detected_from_signature(dir, signature))
+        .collect()
+}
+
+fn detect_game_with_fallback(dir: &Path, max_depth: usize) -> Option<DetectedGameInfo> {
+    let mut detected = None;
+    for_each_scan_dir(dir, max_depth, |candidate| {
+        detected = detect_game_in_dir(candidate);
+        detected.is_some()
+    });
+    detected
 }
 
-fn detect_game_with_fallback(dir: &Path) -> Option<DetectedGameInfo> {
-    if let Some(detected) = detect_game_in_dir(dir) {
-        return Some(detected);
+/// Visits `dir`, `dir/package/bin`, then subdirectories up to `max_depth` levels deep,
+/// stopping as soon as `visit` returns true.
+fn for_each_scan_dir(dir: &Path, max_depth: usize, mut visit: impl FnMut(&Path) -> bool) {
+    if visit(dir) {
+        return;
     }
 
     let package_bin = dir.join("package").join("bin");
-    if let Some(detected) = detect_game_in_dir(&package_bin) {
-        return Some(detected);
+    if visit(&package_bin) {
+        return;
     }
 
-    let mut subdirs = Vec::new();
-    if let Ok(entries) = std::fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                subdirs.push(path);
+    // Breadth-first so shallower installs win; each level is sorted to keep the result stable.
+    let mut level = vec![dir.to_path_buf()];
+    for _ in 0..max_depth {
+        let mut subdirs = Vec::new();
+        for parent in &level {
+            let Ok(entries) = std::fs::read_dir(parent) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                // Directory symlinks and junctions are skipped so loops can't stall the scan.
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() && !file_type.is_symlink() {
+                    subdirs.push(entry.path());
+                }
             }
         }
-    }
-    subdirs.sort_by_key(|p| p.to_string_lossy().to_lowercase());
+        subdirs.sort_by_key(|p| p.to_string_lossy().to_lowercase());
 
-    for subdir in subdirs {
-        if let Some(detected) = detect_game_in_dir(&subdir) {
-            return Some(detected);
+        for subdir in &subdirs {
+            if visit(subdir) {
+                return;
+            }
+        }
+        if subdirs.is_empty() {
+            break;
         }
+        level = subdirs;
     }
-
-    None
 }
 
 fn build_folder_game(detected: DetectedGameInfo) -> Game {
@@ -527,5 +1612,9 @@ fn build_folder_game(detected: DetectedGameInfo) -> Game {
         enabled: true,
         tags: vec![],
         launch_mode: LaunchMode::Folder,
+        order: None,
+        env: Default::default(),
+        pre_launch: None,
+        post_exit: None,
     }
 }
@@ -1,15 +1,17 @@
 use crate::fsdecrypt::crypto::GameKeys;
+use crate::http::{build_client, HttpOptions};
+use crate::trusted::sha256_hex;
 use anyhow::{anyhow, Result};
-use reqwest::blocking::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const DEFAULT_KEYS_FILE: &str = "fsdecrypt_keys.json";
-const KEYS_TIMEOUT_SECS: u64 = 30;
-const KEYS_CONNECT_TIMEOUT_SECS: u64 = 10;
+const KEYS_CACHE_FILE: &str = "fsdecrypt_keys_cache.json";
+const KEYS_CACHE_TTL_SECS: u64 = 600;
 
 #[derive(Debug, Deserialize)]
 struct KeyPair {
@@ -24,11 +26,15 @@ struct GameKeyEntry {
     iv: Option<String>,
 }
 
+/// Layout of `fsdecrypt_keys.json`. `aliases` is optional and maps extra game ids to an
+/// entry in `games`, e.g. `{ "SDHD": "SDEZ" }` for a region variant sharing one key.
 #[derive(Debug, Deserialize)]
 struct KeyFile {
     bootid: KeyPair,
     option: KeyPair,
     games: HashMap<String, GameKeyEntry>,
+    #[serde(default)]
+    aliases: HashMap<String, String>,
 }
 
 #[derive(Clone)]
@@ -38,6 +44,7 @@ pub struct FsDecryptKeys {
     pub option_key: [u8; 16],
     pub option_iv: [u8; 16],
     games: HashMap<String, GameKeys>,
+    aliases: HashMap<String, String>,
 }
 
 #[derive(Clone)]
@@ -77,22 +84,115 @@ fn read_keys_from_file(path: &Path) -> Result<(FsDecryptKeys, KeySourceInfo)> {
     ))
 }
 
-fn read_keys_from_url(url: &str) -> Result<(FsDecryptKeys, KeySourceInfo)> {
-    let client = Client::builder()
-        .timeout(Duration::from_secs(KEYS_TIMEOUT_SECS))
-        .connect_timeout(Duration::from_secs(KEYS_CONNECT_TIMEOUT_SECS))
-        .no_proxy()
-        .build()
+/// A key file downloaded from a URL, kept for `KEYS_CACHE_TTL_SECS`.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedKeyFile {
+    fetched_at: u64,
+    body: String,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn keys_cache_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join(KEYS_CACHE_FILE))
+}
+
+fn read_keys_cache() -> HashMap<String, CachedKeyFile> {
+    keys_cache_path()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_keys_cache(cache: &HashMap<String, CachedKeyFile>) {
+    let Some(path) = keys_cache_path() else {
+        return;
+    };
+    if let Ok(data) = serde_json::to_vec_pretty(cache) {
+        let _ = fs::write(path, data);
+    }
+}
+
+fn cached_key_body(url: &str) -> Option<String> {
+    let cache = read_keys_cache();
+    let entry = cache.get(url)?;
+    if now_secs().saturating_sub(entry.fetched_at) > KEYS_CACHE_TTL_SECS {
+        return None;
+    }
+    Some(entry.body.clone())
+}
+
+fn update_keys_cache(url: &str, body: Option<String>) {
+    let mut cache = read_keys_cache();
+    cache.retain(|_, entry| now_secs().saturating_sub(entry.fetched_at) <= KEYS_CACHE_TTL_SECS);
+    match body {
+        Some(body) => {
+            cache.insert(
+                url.to_string(),
+                CachedKeyFile {
+                    fetched_at: now_secs(),
+                    body,
+                },
+            );
+        }
+        None => {
+            cache.remove(url);
+        }
+    }
+    write_keys_cache(&cache);
+}
+
+fn check_keys_sha(body: &[u8], expected_sha256: Option<&str>) -> Result<()> {
+    if let Some(expected) = expected_sha256.map(str::trim).filter(|s| !s.is_empty()) {
+        if !sha256_hex(body).eq_ignore_ascii_case(expected) {
+            return Err(anyhow!("Invalid keys: sha mismatch"));
+        }
+    }
+    Ok(())
+}
+
+fn parse_keys_body(body: &[u8]) -> Result<FsDecryptKeys> {
+    let parsed: KeyFile = serde_json::from_slice(body)
+        .map_err(|e| anyhow!("Failed to parse keys json: {e}"))?;
+    parse_key_file(parsed)
+}
+
+fn read_keys_from_url(url: &str, expected_sha256: Option<&str>) -> Result<(FsDecryptKeys, KeySourceInfo)> {
+    if let Some(body) = cached_key_body(url) {
+        if check_keys_sha(body.as_bytes(), expected_sha256).is_ok() {
+            match parse_keys_body(body.as_bytes()) {
+                Ok(keys) => {
+                    let game_count = keys.games.len();
+                    return Ok((
+                        keys,
+                        KeySourceInfo {
+                            source: format!("url:{url} (cached)"),
+                            game_count,
+                        },
+                    ));
+                }
+                Err(_) => update_keys_cache(url, None),
+            }
+        }
+    }
+
+    let client = build_client(HttpOptions::default())
         .map_err(|e| anyhow!("Failed to create HTTP client: {e}"))?;
     let resp = client.get(url).send()
         .map_err(|e| anyhow!("Failed to download keys json: {e}"))?;
     if !resp.status().is_success() {
         return Err(anyhow!("Failed to download keys json: {}", resp.status()));
     }
-    let text = resp.text().map_err(|e| anyhow!("Failed to read keys json: {e}"))?;
-    let parsed: KeyFile = serde_json::from_str(&text)
-        .map_err(|e| anyhow!("Failed to parse keys json: {e}"))?;
-    let keys = parse_key_file(parsed)?;
+    let body = resp.bytes().map_err(|e| anyhow!("Failed to read keys json: {e}"))?;
+    check_keys_sha(&body, expected_sha256)?;
+    let keys = parse_keys_body(&body)?;
+    update_keys_cache(url, Some(String::from_utf8_lossy(&body).into_owned()));
     let game_count = keys.games.len();
     Ok((
         keys,
@@ -119,12 +219,22 @@ fn parse_key_file(parsed: KeyFile) -> Result<FsDecryptKeys> {
         games.insert(id.trim().to_uppercase(), GameKeys { key, iv });
     }
 
+    let mut aliases = HashMap::new();
+    for (alias, target) in parsed.aliases {
+        let target = target.trim().to_uppercase();
+        if !games.contains_key(&target) {
+            return Err(anyhow!("Alias {alias} points to unknown game {target}"));
+        }
+        aliases.insert(alias.trim().to_uppercase(), target);
+    }
+
     Ok(FsDecryptKeys {
         bootid_key,
         bootid_iv,
         option_key,
         option_iv,
         games,
+        aliases,
     })
 }
 
@@ -148,20 +258,44 @@ fn resolve_local_keys_file() -> Result<PathBuf> {
     ))
 }
 
-pub fn load_keys(key_url: Option<&str>) -> Result<(FsDecryptKeys, KeySourceInfo)> {
+/// Loads keys from `key_url`, or from the local key file when no URL is given.
+/// `key_sha256`, when set, must match the downloaded file.
+pub fn load_keys(key_url: Option<&str>, key_sha256: Option<&str>) -> Result<(FsDecryptKeys, KeySourceInfo)> {
     if let Some(url) = key_url {
         let trimmed = url.trim();
         if !trimmed.is_empty() {
-            return read_keys_from_url(trimmed);
+            return read_keys_from_url(trimmed, key_sha256);
         }
     }
     let local_path = resolve_local_keys_file()?;
     read_keys_from_file(&local_path)
 }
 
+/// Skeleton `fsdecrypt_keys.json` with empty placeholders for `game_ids`.
+pub fn keys_template(game_ids: &[String]) -> Value {
+    let mut games = Map::new();
+    for id in game_ids {
+        let id = id.trim().to_uppercase();
+        if !id.is_empty() {
+            games.insert(id, json!({ "key": "", "iv": "" }));
+        }
+    }
+    json!({
+        "_comment": "Each key and iv is 16 bytes written as 32 hex characters (a 0x prefix is allowed). \
+                     A game's iv may be omitted. aliases maps extra game ids to an entry in games.",
+        "bootid": { "key": "", "iv": "" },
+        "option": { "key": "", "iv": "" },
+        "games": games,
+        "aliases": {},
+    })
+}
+
 impl FsDecryptKeys {
     pub fn game_keys_for(&self, game_id: &str) -> Option<GameKeys> {
         let key = game_id.trim().to_uppercase();
-        self.games.get(&key).cloned()
+        self.games
+            .get(&key)
+            .or_else(|| self.aliases.get(&key).and_then(|target| self.games.get(target)))
+            .cloned()
     }
 }
@@ -1,6 +1,7 @@
 use std::{
     any::Any,
-    fs::{create_dir_all, File, FileTimes},
+    collections::HashSet,
+    fs::{create_dir_all, rename, File, FileTimes, OpenOptions},
     io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     time::{Duration, Instant, SystemTime},
@@ -24,12 +25,18 @@ use self::{
     crypto::{calculate_file_iv, calculate_page_iv, Aes128CbcDec, GameKeys, EXFAT_HEADER, NTFS_HEADER},
     keys::{load_keys, FsDecryptKeys},
 };
+use crate::trusted::HashingWriter;
 
 mod bootid;
 mod crypto;
 mod keys;
+mod space;
+
+pub use self::keys::keys_template;
+pub use self::space::DecryptSpaceTarget;
 
 const PAGE_SIZE: u64 = 4096;
+const DECRYPT_MANIFEST_FILE: &str = "decrypt_manifest.json";
 
 #[derive(Serialize, Clone)]
 pub struct DecryptResult {
@@ -38,15 +45,93 @@ pub struct DecryptResult {
     pub container_type: Option<String>,
     pub extracted: bool,
     pub warnings: Vec<String>,
+    /// Not decrypted because its output already exists (`skip_existing`).
+    pub skipped: bool,
     pub failed: bool,
     pub error: Option<String>,
 }
 
+/// Options for [`decrypt_game_files`].
+#[derive(Clone, Debug, Default)]
+pub struct DecryptOptions {
+    /// Keep the raw `.ntfs`/`.exfat` image instead of extracting it.
+    pub no_extract: bool,
+    /// Where output goes; next to each container when `None`.
+    pub output_dir: Option<PathBuf>,
+    /// Skip containers whose output already exists.
+    pub skip_existing: bool,
+    /// Write `decrypt_manifest.json` with the size and SHA-256 of every output file.
+    /// Containers skipped by `skip_existing` are not listed.
+    pub write_manifest: bool,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct DecryptManifest {
+    generated_at: String,
+    files: Vec<ManifestEntry>,
+}
+
+/// SHA-256 of each final output file, computed while it is written.
+struct OutputDigests {
+    enabled: bool,
+    entries: Vec<(PathBuf, String)>,
+}
+
+impl OutputDigests {
+    fn writer<W: Write>(&self, inner: W) -> HashingWriter<W> {
+        if self.enabled {
+            HashingWriter::new(inner)
+        } else {
+            HashingWriter::disabled(inner)
+        }
+    }
+
+    fn record(&mut self, path: &Path, sha256: Option<String>) {
+        if let Some(sha256) = sha256 {
+            self.entries.push((path.to_path_buf(), sha256));
+        }
+    }
+
+    /// Writes the manifest into `dir`, with output paths relative to it where possible.
+    fn write_manifest(&self, dir: &Path) -> Result<PathBuf> {
+        let files = self
+            .entries
+            .iter()
+            .map(|(path, sha256)| ManifestEntry {
+                path: path
+                    .strip_prefix(dir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .into_owned(),
+                size: path.metadata().map(|meta| meta.len()).unwrap_or(0),
+                sha256: sha256.clone(),
+            })
+            .collect();
+        let manifest = DecryptManifest {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            files,
+        };
+        create_dir_all(dir)?;
+        let path = dir.join(DECRYPT_MANIFEST_FILE);
+        std::fs::write(&path, serde_json::to_vec_pretty(&manifest)?)?;
+        Ok(path)
+    }
+}
+
 #[derive(Serialize, Clone)]
 pub struct DecryptSummary {
     pub results: Vec<DecryptResult>,
     pub key_source: String,
     pub key_game_count: usize,
+    /// Path of the written `decrypt_manifest.json` (`write_manifest`).
+    pub manifest: Option<String>,
 }
 
 #[derive(Serialize, Clone)]
@@ -94,23 +179,103 @@ fn exfat_timestamp_to_system_time(timestamp: &exfat_fs::timestamp::Timestamp) ->
         + Duration::from_micros(chrono_date_time.timestamp_micros().try_into()?))
 }
 
-fn extract_exfat_contents(exfat_path: &Path) -> Result<PathBuf> {
+fn extract_exfat_contents(
+    exfat_path: &Path,
+    warnings: &mut Vec<String>,
+    digests: &mut OutputDigests,
+) -> Result<PathBuf> {
     let output_dir = exfat_path.with_extension("");
     let file = File::open(exfat_path)?;
     let mut root = Root::open(file)?;
 
+    // The marker stays behind if extraction is cut short, so the folder is not taken
+    // for finished output.
+    let marker = partial_path(&output_dir);
+    File::create(&marker)?;
     create_dir_all(&output_dir)?;
-    extract_exfat_elements(root.items(), &output_dir)?;
+    extract_exfat_elements(root.items(), &output_dir, warnings, digests);
+    std::fs::remove_file(&marker)?;
 
     Ok(output_dir)
 }
 
-fn extract_exfat_elements(elements: &mut [FsElement<File>], output_dir: &Path) -> Result<()> {
+const RESERVED_WINDOWS_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Makes an exFAT entry name usable as a Windows file name: reserved characters become
+/// `_`, trailing dots and spaces are dropped and device names such as `CON` get a `_` prefix.
+fn sanitize_exfat_name(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let trimmed = replaced.trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() {
+        return "_".to_string();
+    }
+    let stem = trimmed.split('.').next().unwrap_or(trimmed).trim_end();
+    if RESERVED_WINDOWS_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        return format!("_{trimmed}");
+    }
+    trimmed.to_string()
+}
+
+/// Returns `name`, or `name (n)` when a sibling already uses it (case-insensitively).
+fn unique_exfat_name(name: String, used: &mut HashSet<String>) -> String {
+    if used.insert(name.to_lowercase()) {
+        return name;
+    }
+    let (stem, ext) = match name.rfind('.') {
+        Some(idx) if idx > 0 => (&name[..idx], &name[idx..]),
+        _ => (name.as_str(), ""),
+    };
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{stem} ({counter}){ext}");
+        if used.insert(candidate.to_lowercase()) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Extracts `elements` into `output_dir`. Entries that are renamed or fail to extract are
+/// reported in `warnings` and do not stop the rest of the extraction.
+fn extract_exfat_elements(
+    elements: &mut [FsElement<File>],
+    output_dir: &Path,
+    warnings: &mut Vec<String>,
+    digests: &mut OutputDigests,
+) {
+    let mut used = HashSet::new();
     for element in elements {
-        match element {
-            FsElement::F(ref mut file) => {
-                let dest_path = output_dir.join(file.name());
-                let mut dest = File::create(dest_path)?;
+        let name = match element {
+            FsElement::F(file) => file.name().to_string(),
+            FsElement::D(directory) => directory.name().to_string(),
+        };
+        let dest_name = unique_exfat_name(sanitize_exfat_name(&name), &mut used);
+        if dest_name != name {
+            warnings.push(format!(
+                "Renamed {} to {} in {}",
+                name,
+                dest_name,
+                output_dir.display()
+            ));
+        }
+        let dest_path = output_dir.join(&dest_name);
+
+        let outcome = match element {
+            FsElement::F(ref mut file) => (|| -> Result<()> {
+                let mut dest = File::create(&dest_path)?;
 
                 dest.set_times(
                     FileTimes::new()
@@ -122,21 +287,52 @@ fn extract_exfat_elements(elements: &mut [FsElement<File>], output_dir: &Path) -
                         )?),
                 )?;
 
-                let mut writer = BufWriter::with_capacity(256 * 1024, &mut dest);
+                let mut writer = digests.writer(BufWriter::with_capacity(256 * 1024, &mut dest));
 
                 std::io::copy(file, &mut writer)?;
-            }
-            FsElement::D(directory) => {
-                let dest_path = output_dir.join(directory.name());
+                writer.flush()?;
+                let sha256 = writer.finish();
+                digests.record(&dest_path, sha256);
+                Ok(())
+            })(),
+            FsElement::D(directory) => (|| -> Result<()> {
                 create_dir_all(&dest_path)?;
 
                 let mut children = directory.open()?;
-                extract_exfat_elements(&mut children, &dest_path)?;
-            }
+                extract_exfat_elements(&mut children, &dest_path, warnings, digests);
+
+                // Applied after the children so creating them does not bump the times.
+                set_dir_times(
+                    &dest_path,
+                    FileTimes::new()
+                        .set_accessed(exfat_timestamp_to_system_time(
+                            directory.timestamps().accessed(),
+                        )?)
+                        .set_modified(exfat_timestamp_to_system_time(
+                            directory.timestamps().modified(),
+                        )?),
+                )?;
+                Ok(())
+            })(),
+        };
+        if let Err(e) = outcome {
+            warnings.push(format!("Failed to extract {}: {e:#}", dest_path.display()));
         }
     }
+}
 
-    Ok(())
+fn set_dir_times(path: &Path, times: FileTimes) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        // Directories can only be opened with backup semantics.
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        options.write(true).custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+    }
+    #[cfg(not(windows))]
+    options.read(true);
+    options.open(path)?.set_times(times)
 }
 
 fn ntfs_time_to_system_time(ntfs_time: NtfsTime) -> SystemTime {
@@ -147,9 +343,36 @@ fn ntfs_time_to_system_time(ntfs_time: NtfsTime) -> SystemTime {
     SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos_since_unix_epoch)
 }
 
-fn extract_internal_vhd(image_path: &Path, sequence_number: u8) -> Result<PathBuf> {
+/// Names of the `internal_*.vhd` files in the image's root directory.
+fn internal_vhd_names(fs: &mut File, ntfs: &Ntfs) -> Result<Vec<String>> {
+    let root_directory = ntfs.root_directory(fs)?;
+    let index = root_directory.directory_index(fs)?;
+    let mut entries = index.entries();
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next(fs) {
+        let Some(key) = entry?.key() else {
+            continue;
+        };
+        let name = key?.name().to_string_lossy();
+        let lower = name.to_lowercase();
+        if lower.starts_with("internal_") && lower.ends_with(".vhd") && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// Extracts `internal_{sequence_number}.vhd` from the decrypted NTFS image. Returns
+/// `Ok(None)` when the image carries no internal VHD at all, and an error when it has
+/// internal VHDs but not the one this container's sequence number refers to.
+fn extract_internal_vhd(
+    image_path: &Path,
+    sequence_number: u8,
+    digests: &mut OutputDigests,
+) -> Result<Option<PathBuf>> {
     let vhd_filename = format!("internal_{sequence_number}.vhd");
     let output_path = image_path.with_extension("vhd");
+    let partial = partial_path(&output_path);
 
     let mut fs = File::open(image_path)?;
     let mut ntfs = Ntfs::new(&mut fs)?;
@@ -158,8 +381,17 @@ fn extract_internal_vhd(image_path: &Path, sequence_number: u8) -> Result<PathBu
     let root_directory = ntfs.root_directory(&mut fs)?;
     let index = root_directory.directory_index(&mut fs)?;
     let mut finder = index.finder();
-    let entry = NtfsFileNameIndex::find(&mut finder, &ntfs, &mut fs, &vhd_filename)
-        .ok_or_else(|| anyhow!("could not find VHD {vhd_filename}"))??;
+    let Some(entry) = NtfsFileNameIndex::find(&mut finder, &ntfs, &mut fs, &vhd_filename) else {
+        let present = internal_vhd_names(&mut fs, &ntfs)?;
+        if present.is_empty() {
+            return Ok(None);
+        }
+        return Err(anyhow!(
+            "expected {vhd_filename} for sequence {sequence_number}, but the image only contains {}",
+            present.join(", ")
+        ));
+    };
+    let entry = entry?;
     let file = entry.to_file(&ntfs, &mut fs)?;
     let data_item = file
         .data(&mut fs, "")
@@ -167,12 +399,12 @@ fn extract_internal_vhd(image_path: &Path, sequence_number: u8) -> Result<PathBu
     let data_attribute = data_item.to_attribute()?;
     let mut data_value = data_attribute.value(&mut fs)?.attach(&mut fs);
 
-    let mut output_file = File::create(&output_path)?;
-    let mut writer = BufWriter::with_capacity(256 * 1024, &mut output_file);
+    let mut output_file = File::create(&partial)?;
+    let mut writer = digests.writer(BufWriter::with_capacity(256 * 1024, &mut output_file));
 
     std::io::copy(&mut data_value, &mut writer)?;
     writer.flush()?;
-    drop(writer);
+    let sha256 = writer.finish();
 
     let mut attributes_iterator = file.attributes();
 
@@ -196,7 +428,10 @@ fn extract_internal_vhd(image_path: &Path, sequence_number: u8) -> Result<PathBu
         }
     }
 
-    Ok(output_path)
+    drop(output_file);
+    rename(&partial, &output_path)?;
+    digests.record(&output_path, sha256);
+    Ok(Some(output_path))
 }
 
 fn normalize_id(bytes: &[u8]) -> Result<String> {
@@ -215,7 +450,35 @@ fn read_bootid_from_reader(reader: &mut BufReader<File>, keys: &FsDecryptKeys) -
         .decrypt_padded_mut::<NoPadding>(&mut bootid_bytes)
         .map_err(|e| anyhow!("Could not decrypt BootID: {e:#?}"))?;
 
-    Ok(unsafe { std::ptr::read_unaligned(bootid_bytes.as_ptr() as *const BootId) })
+    let bootid = unsafe { std::ptr::read_unaligned(bootid_bytes.as_ptr() as *const BootId) };
+    let container_len = reader.get_ref().metadata()?.len();
+    validate_bootid(&bootid, container_len)?;
+    Ok(bootid)
+}
+
+/// Rejects BootIDs whose geometry cannot describe `container_len` bytes, which is what a
+/// corrupt header or a wrong BootID key looks like, before any output is allocated.
+fn validate_bootid(bootid: &BootId, container_len: u64) -> Result<()> {
+    if bootid.block_size == 0 {
+        return Err(anyhow!("Invalid BootID: block size is 0 (wrong key or corrupt file?)"));
+    }
+    if bootid.header_block_count >= bootid.block_count {
+        return Err(anyhow!(
+            "Invalid BootID: header blocks ({}) not less than total blocks ({}) (wrong key or corrupt file?)",
+            bootid.header_block_count,
+            bootid.block_count
+        ));
+    }
+    let declared = bootid.block_count.checked_mul(bootid.block_size);
+    match declared {
+        Some(declared) if declared <= container_len => Ok(()),
+        _ => Err(anyhow!(
+            "Invalid BootID: {} blocks of {} bytes exceed the {} byte container (wrong key or corrupt file?)",
+            bootid.block_count,
+            bootid.block_size,
+            container_len
+        )),
+    }
 }
 
 fn output_size_from_bootid(bootid: &BootId) -> u64 {
@@ -225,17 +488,17 @@ fn output_size_from_bootid(bootid: &BootId) -> u64 {
         .saturating_mul(bootid.block_size)
 }
 
-fn decrypt_container(
-    path: &Path,
-    no_extract: bool,
-    keys: &FsDecryptKeys,
-    result: &mut DecryptResult,
-    mut progress: Option<&mut dyn FnMut(u64)>,
-) -> Result<()> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::with_capacity(0x40000, file);
+struct ContainerInfo {
+    bootid: BootId,
+    os_id: String,
+    game_id: String,
+    output_filename: String,
+    output_size: u64,
+}
 
-    let bootid = read_bootid_from_reader(&mut reader, keys)?;
+/// Reads the BootID and works out what decrypting the container would produce.
+fn inspect_container(reader: &mut BufReader<File>, keys: &FsDecryptKeys) -> Result<ContainerInfo> {
+    let bootid = read_bootid_from_reader(reader, keys)?;
 
     if bootid.container_type != ContainerType::OS
         && bootid.container_type != ContainerType::APP
@@ -246,49 +509,6 @@ fn decrypt_container(
 
     let os_id = normalize_id(&bootid.os_id)?;
     let game_id = normalize_id(&bootid.game_id)?;
-    let id = match bootid.container_type {
-        ContainerType::OS => os_id.clone(),
-        _ => game_id.clone(),
-    };
-
-    let keys = match bootid.container_type {
-        ContainerType::OS => keys
-            .game_keys_for(&os_id)
-            .ok_or_else(|| anyhow!("Key not found for {id}"))?,
-        ContainerType::APP => keys
-            .game_keys_for(&game_id)
-            .ok_or_else(|| anyhow!("Key not found for {id}"))?,
-        _ => GameKeys {
-            key: keys.option_key,
-            iv: Some(keys.option_iv),
-        },
-    };
-
-    result.container_type = Some(match bootid.container_type {
-        ContainerType::OS => "OS",
-        ContainerType::APP => "APP",
-        ContainerType::OPTION => "OPTION",
-        _ => "UNKNOWN",
-    }
-    .to_string());
-
-    let data_offset = bootid.header_block_count * bootid.block_size;
-    let key = keys.key;
-    let iv = if bootid.use_custom_iv { None } else { keys.iv };
-    let iv = match iv {
-        Some(iv) => iv,
-        None => {
-            reader.seek(SeekFrom::Start(data_offset))?;
-            let mut page: Vec<u8> = Vec::with_capacity(PAGE_SIZE as usize);
-            Read::by_ref(&mut reader).take(4096).read_to_end(&mut page)?;
-
-            if bootid.container_type == ContainerType::OPTION {
-                calculate_file_iv(key, EXFAT_HEADER, &page)?
-            } else {
-                calculate_file_iv(key, NTFS_HEADER, &page)?
-            }
-        }
-    };
 
     let output_filename = match bootid.container_type {
         ContainerType::OS => format!(
@@ -333,13 +553,134 @@ fn decrypt_container(
             )
         }
     };
-    let output_path = path.with_file_name(&output_filename);
-    let output_file = File::create(&output_path)?;
-    let output_size = output_size_from_bootid(&bootid);
+
+    Ok(ContainerInfo {
+        output_size: output_size_from_bootid(&bootid),
+        bootid,
+        os_id,
+        game_id,
+        output_filename,
+    })
+}
+
+fn output_path_for(path: &Path, output_dir: Option<&Path>, output_filename: &str) -> PathBuf {
+    // Extracted VHDs and exfat folders are placed next to this intermediate image.
+    match output_dir {
+        Some(dir) => dir.join(output_filename),
+        None => path.with_file_name(output_filename),
+    }
+}
+
+/// Where a file is written until it is complete, or the marker that sits next to an
+/// extracted folder while it is being filled.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+/// Output left by an earlier run: the raw image at its full size or, when extracting,
+/// the extracted VHD or folder. Files only appear under their final name once complete
+/// and a folder counts only without its `.partial` marker, so interrupted output is
+/// decrypted again.
+fn existing_output(output_path: &Path, info: &ContainerInfo, no_extract: bool) -> Option<PathBuf> {
+    let raw_complete = output_path
+        .metadata()
+        .map(|meta| meta.is_file() && meta.len() == info.output_size)
+        .unwrap_or(false);
+    if raw_complete {
+        return Some(output_path.to_path_buf());
+    }
+    if no_extract {
+        return None;
+    }
+    match info.bootid.container_type {
+        ContainerType::OPTION => {
+            Some(output_path.with_extension("")).filter(|p| p.is_dir() && !partial_path(p).exists())
+        }
+        _ => Some(output_path.with_extension("vhd")).filter(|p| p.is_file()),
+    }
+}
+
+fn decrypt_container(
+    path: &Path,
+    options: &DecryptOptions,
+    keys: &FsDecryptKeys,
+    result: &mut DecryptResult,
+    digests: &mut OutputDigests,
+    mut progress: Option<&mut dyn FnMut(u64)>,
+) -> Result<()> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::with_capacity(0x40000, file);
+
+    let info = inspect_container(&mut reader, keys)?;
+    let bootid = info.bootid;
+    let os_id = info.os_id.clone();
+    let game_id = info.game_id.clone();
+    let id = match bootid.container_type {
+        ContainerType::OS => os_id.clone(),
+        _ => game_id.clone(),
+    };
+
+    result.container_type = Some(match bootid.container_type {
+        ContainerType::OS => "OS",
+        ContainerType::APP => "APP",
+        ContainerType::OPTION => "OPTION",
+        _ => "UNKNOWN",
+    }
+    .to_string());
+
+    if options.skip_existing {
+        let output_path = output_path_for(path, options.output_dir.as_deref(), &info.output_filename);
+        if let Some(existing) = existing_output(&output_path, &info, options.no_extract) {
+            result.output = Some(existing.to_string_lossy().into_owned());
+            result.skipped = true;
+            return Ok(());
+        }
+    }
+
+    let keys = match bootid.container_type {
+        ContainerType::OS => keys
+            .game_keys_for(&os_id)
+            .ok_or_else(|| anyhow!("Key not found for {id}"))?,
+        ContainerType::APP => keys
+            .game_keys_for(&game_id)
+            .ok_or_else(|| anyhow!("Key not found for {id}"))?,
+        _ => GameKeys {
+            key: keys.option_key,
+            iv: Some(keys.option_iv),
+        },
+    };
+
+    let data_offset = bootid.header_block_count * bootid.block_size;
+    let key = keys.key;
+    let iv = if bootid.use_custom_iv { None } else { keys.iv };
+    let iv = match iv {
+        Some(iv) => iv,
+        None => {
+            reader.seek(SeekFrom::Start(data_offset))?;
+            let mut page: Vec<u8> = Vec::with_capacity(PAGE_SIZE as usize);
+            Read::by_ref(&mut reader).take(4096).read_to_end(&mut page)?;
+
+            if bootid.container_type == ContainerType::OPTION {
+                calculate_file_iv(key, EXFAT_HEADER, &page)?
+            } else {
+                calculate_file_iv(key, NTFS_HEADER, &page)?
+            }
+        }
+    };
+
+    let output_path = output_path_for(path, options.output_dir.as_deref(), &info.output_filename);
+    if let Some(dir) = options.output_dir.as_deref() {
+        create_dir_all(dir)?;
+    }
+    let partial = partial_path(&output_path);
+    let output_file = File::create(&partial)?;
+    let output_size = info.output_size;
 
     output_file.set_len(output_size)?;
 
-    let mut writer = BufWriter::with_capacity(0x40000, output_file);
+    let mut writer = digests.writer(BufWriter::with_capacity(0x40000, output_file));
     let cipher = Aes128Dec::new_from_slice(&key).map_err(|e| anyhow!(e))?;
     let mut page: Vec<u8> = Vec::with_capacity(PAGE_SIZE as usize);
     let mut page_iv = [0u8; 16];
@@ -375,30 +716,44 @@ fn decrypt_container(
     }
 
     writer.flush()?;
+    let raw_sha256 = writer.finish();
+    rename(&partial, &output_path)?;
     if let Some(ref mut report) = progress {
         if processed != last_reported {
             report(processed);
         }
     }
 
-    if no_extract {
+    if options.no_extract {
         result.output = Some(output_path.to_string_lossy().into_owned());
+        digests.record(&output_path, raw_sha256);
         return Ok(());
     }
 
     match bootid.container_type {
-        ContainerType::OS | ContainerType::APP => match extract_internal_vhd(&output_path, bootid.sequence_number) {
-            Ok(vhd_path) => {
+        ContainerType::OS | ContainerType::APP => match extract_internal_vhd(&output_path, bootid.sequence_number, digests) {
+            Ok(Some(vhd_path)) => {
                 let _ = std::fs::remove_file(&output_path);
                 result.output = Some(vhd_path.to_string_lossy().into_owned());
                 result.extracted = true;
             }
+            Ok(None) => {
+                // Some APP containers ship their files directly in the NTFS image.
+                result.output = Some(output_path.to_string_lossy().into_owned());
+                digests.record(&output_path, raw_sha256);
+                if bootid.container_type == ContainerType::OS {
+                    result.warnings.push(
+                        "OS container has no internal VHD; kept the decrypted NTFS image".to_string(),
+                    );
+                }
+            }
             Err(e) => {
                 result.output = Some(output_path.to_string_lossy().into_owned());
+                digests.record(&output_path, raw_sha256);
                 result.warnings.push(format!("Failed to extract internal VHD: {e:#}"));
             }
         },
-        ContainerType::OPTION => match extract_exfat_contents(&output_path) {
+        ContainerType::OPTION => match extract_exfat_contents(&output_path, &mut result.warnings, digests) {
             Ok(dir) => {
                 let _ = std::fs::remove_file(&output_path);
                 result.output = Some(dir.to_string_lossy().into_owned());
@@ -406,50 +761,92 @@ fn decrypt_container(
             }
             Err(e) => {
                 result.output = Some(output_path.to_string_lossy().into_owned());
+                digests.record(&output_path, raw_sha256);
                 result.warnings.push(format!("Failed to extract exfat contents: {e:#}"));
             }
         },
         _ => {
             result.output = Some(output_path.to_string_lossy().into_owned());
+            digests.record(&output_path, raw_sha256);
         }
     }
 
     Ok(())
 }
 
-pub fn decrypt_game_files(
-    files: Vec<PathBuf>,
-    no_extract: bool,
-    key_url: Option<String>,
-    mut progress: Option<&mut dyn FnMut(DecryptProgress)>,
-    mut on_result: Option<&mut dyn FnMut(DecryptResult)>,
-) -> Result<DecryptSummary> {
-    let (keys, info) = load_keys(key_url.as_deref())?;
-    let mut results = Vec::new();
-
-    let mut file_sizes = Vec::new();
-    let mut total_bytes = 0u64;
-    if progress.is_some() {
-        for path in &files {
-            let estimated = (|| -> Result<u64> {
+/// Expected decrypted size of each file, falling back to the container size when its
+/// BootID cannot be read. Files that `skip_existing` will skip count as 0.
+fn estimate_output_sizes(files: &[PathBuf], keys: &FsDecryptKeys, options: &DecryptOptions) -> Vec<u64> {
+    files
+        .iter()
+        .map(|path| {
+            (|| -> Result<u64> {
                 let file = File::open(path)?;
                 let mut reader = BufReader::with_capacity(0x40000, file);
-                let bootid = read_bootid_from_reader(&mut reader, &keys)?;
-                Ok(output_size_from_bootid(&bootid))
+                let info = inspect_container(&mut reader, keys)?;
+                if options.skip_existing {
+                    let output_path = output_path_for(path, options.output_dir.as_deref(), &info.output_filename);
+                    if existing_output(&output_path, &info, options.no_extract).is_some() {
+                        return Ok(0);
+                    }
+                }
+                Ok(info.output_size)
             })()
             .or_else(|_| {
                 path.metadata()
                     .map(|meta| meta.len())
                     .map_err(|e| anyhow!(e))
             })
-            .unwrap_or(0);
-            file_sizes.push(estimated);
-            total_bytes = total_bytes.saturating_add(estimated);
-        }
-        if total_bytes == 0 {
-            total_bytes = 1;
-        }
+            .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Disk space the decrypted images of `files` need, per output directory.
+pub fn estimate_decrypt_space(
+    files: Vec<PathBuf>,
+    output_dir: Option<PathBuf>,
+    key_url: Option<String>,
+    key_sha256: Option<String>,
+) -> Result<Vec<DecryptSpaceTarget>> {
+    let (keys, _info) = load_keys(key_url.as_deref(), key_sha256.as_deref())?;
+    let options = DecryptOptions {
+        output_dir,
+        ..Default::default()
+    };
+    let sizes = estimate_output_sizes(&files, &keys, &options);
+    Ok(space::space_targets(&files, &sizes, options.output_dir.as_deref(), !options.no_extract))
+}
+
+/// Decrypts `files` with the keys from `key_url` (or the local key file).
+pub fn decrypt_game_files(
+    files: Vec<PathBuf>,
+    options: DecryptOptions,
+    key_url: Option<String>,
+    key_sha256: Option<String>,
+    mut progress: Option<&mut dyn FnMut(DecryptProgress)>,
+    mut on_result: Option<&mut dyn FnMut(DecryptResult)>,
+) -> Result<DecryptSummary> {
+    let (keys, info) = load_keys(key_url.as_deref(), key_sha256.as_deref())?;
+    let mut results = Vec::new();
+
+    let file_sizes = estimate_output_sizes(&files, &keys, &options);
+    let extract = !options.no_extract;
+    if let Some(short) = space::space_targets(&files, &file_sizes, options.output_dir.as_deref(), extract)
+        .into_iter()
+        .find(|target| !target.sufficient)
+    {
+        return Err(anyhow!(
+            "Not enough disk space in {}: {} bytes needed, {} bytes available",
+            short.dir,
+            short.required,
+            short.available.unwrap_or(0)
+        ));
     }
+    let total_bytes = file_sizes
+        .iter()
+        .fold(0u64, |total, size| total.saturating_add(*size))
+        .max(1);
 
     let mut processed_total: u64 = 0;
     let mut last_percent: u8 = 0;
@@ -480,6 +877,11 @@ pub fn decrypt_game_files(
     };
 
     let total_files = files.len();
+    let files_dir = files.first().and_then(|path| path.parent()).map(Path::to_path_buf);
+    let mut digests = OutputDigests {
+        enabled: options.write_manifest,
+        entries: Vec::new(),
+    };
     if progress.is_some() {
         emit_progress(&mut progress, processed_total, 0, total_files, true);
     }
@@ -490,6 +892,7 @@ pub fn decrypt_game_files(
             container_type: None,
             extracted: false,
             warnings: Vec::new(),
+            skipped: false,
             failed: false,
             error: None,
         };
@@ -519,7 +922,7 @@ pub fn decrypt_game_files(
         };
 
         let decrypt_outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            decrypt_container(&path, no_extract, &keys, &mut entry, progress_ref)
+            decrypt_container(&path, &options, &keys, &mut entry, &mut digests, progress_ref)
         }));
         match decrypt_outcome {
             Ok(Ok(())) => {}
@@ -562,17 +965,83 @@ pub fn decrypt_game_files(
         emit_progress(&mut progress, processed_total, total_files, total_files, true);
     }
 
+    let manifest = match options.output_dir.clone().or(files_dir) {
+        Some(dir) if options.write_manifest => Some(digests.write_manifest(&dir)?.to_string_lossy().into_owned()),
+        _ => None,
+    };
+
     Ok(DecryptSummary {
         results,
         key_source: info.source,
         key_game_count: info.game_count,
+        manifest,
     })
 }
 
-pub fn load_key_status(key_url: Option<String>) -> Result<KeyStatus> {
-    let (_keys, info) = load_keys(key_url.as_deref())?;
+pub fn load_key_status(key_url: Option<String>, key_sha256: Option<String>) -> Result<KeyStatus> {
+    let (_keys, info) = load_keys(key_url.as_deref(), key_sha256.as_deref())?;
     Ok(KeyStatus {
         key_source: info.source,
         key_game_count: info.game_count,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_exfat_name_prefixes_reserved_names() {
+        assert_eq!(sanitize_exfat_name("CON"), "_CON");
+        assert_eq!(sanitize_exfat_name("nul"), "_nul");
+        assert_eq!(sanitize_exfat_name("con.txt"), "_con.txt");
+        assert_eq!(sanitize_exfat_name("COM1 .log"), "_COM1 .log");
+        assert_eq!(sanitize_exfat_name("CONSOLE"), "CONSOLE");
+    }
+
+    #[test]
+    fn sanitize_exfat_name_strips_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_exfat_name("data. . "), "data");
+        assert_eq!(sanitize_exfat_name("NUL. "), "_NUL");
+        assert_eq!(sanitize_exfat_name("..."), "_");
+        assert_eq!(sanitize_exfat_name("a<b>:c?.bin"), "a_b__c_.bin");
+    }
+
+    #[test]
+    fn unique_exfat_name_numbers_collisions() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_exfat_name("app.bin".to_string(), &mut used), "app.bin");
+        assert_eq!(unique_exfat_name("APP.bin".to_string(), &mut used), "APP (2).bin");
+        assert_eq!(unique_exfat_name("app.bin".to_string(), &mut used), "app (3).bin");
+        assert_eq!(unique_exfat_name(".hidden".to_string(), &mut used), ".hidden");
+        assert_eq!(unique_exfat_name(".hidden".to_string(), &mut used), ".hidden (2)");
+    }
+
+    fn bootid(block_count: u64, block_size: u64, header_block_count: u64) -> BootId {
+        // SAFETY: BootId is plain old data; all-zero bytes are a valid value.
+        let mut bootid: BootId = unsafe { std::mem::zeroed() };
+        bootid.block_count = block_count;
+        bootid.block_size = block_size;
+        bootid.header_block_count = header_block_count;
+        bootid
+    }
+
+    #[test]
+    fn validate_bootid_accepts_fitting_geometry() {
+        assert!(validate_bootid(&bootid(4, 0x1000, 1), 0x4000).is_ok());
+    }
+
+    #[test]
+    fn validate_bootid_rejects_malformed_header() {
+        assert!(validate_bootid(&bootid(4, 0, 1), 0x4000).is_err());
+        assert!(validate_bootid(&bootid(4, 0x1000, 4), 0x4000).is_err());
+        assert!(validate_bootid(&bootid(0, 0x1000, 0), 0x4000).is_err());
+    }
+
+    #[test]
+    fn validate_bootid_rejects_sizes_beyond_container() {
+        // A container truncated by one block.
+        assert!(validate_bootid(&bootid(4, 0x1000, 1), 0x3fff).is_err());
+        assert!(validate_bootid(&bootid(u64::MAX, 0x1000, 1), u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Space needed for the decrypted images written to one directory.
+#[derive(Serialize, Clone)]
+pub struct DecryptSpaceTarget {
+    pub dir: String,
+    pub required: u64,
+    /// Free space on the volume holding `dir`; `None` when it could not be queried.
+    pub available: Option<u64>,
+    pub sufficient: bool,
+}
+
+/// Groups `sizes` by the directory each output goes to and compares them with the free
+/// space there. With `extract`, a raw image and the VHD or folder extracted from it exist
+/// side by side until the image is deleted, so the largest image is counted once more.
+pub fn space_targets(
+    files: &[PathBuf],
+    sizes: &[u64],
+    output_dir: Option<&Path>,
+    extract: bool,
+) -> Vec<DecryptSpaceTarget> {
+    let mut required: BTreeMap<PathBuf, (u64, u64)> = BTreeMap::new();
+    for (path, size) in files.iter().zip(sizes) {
+        let dir = match output_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        let (total, largest) = required.entry(dir).or_insert((0, 0));
+        *total = total.saturating_add(*size);
+        *largest = (*largest).max(*size);
+    }
+    required
+        .into_iter()
+        .map(|(dir, (total, largest))| {
+            let required = if extract { total.saturating_add(largest) } else { total };
+            let available = available_space(&dir);
+            DecryptSpaceTarget {
+                dir: dir.to_string_lossy().into_owned(),
+                required,
+                available,
+                sufficient: available.is_none_or(|free| free >= required),
+            }
+        })
+        .collect()
+}
+
+/// Free bytes for the current user on the volume holding `dir`, or its nearest existing
+/// ancestor when the directory has not been created yet.
+#[cfg(windows)]
+fn available_space(dir: &Path) -> Option<u64> {
+    use std::iter::once;
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory_name: *const u16,
+            free_bytes_available_to_caller: *mut u64,
+            total_number_of_bytes: *mut u64,
+            total_number_of_free_bytes: *mut u64,
+        ) -> i32;
+    }
+
+    let existing = dir.ancestors().find(|p| p.is_dir())?;
+    let wide: Vec<u16> = existing.as_os_str().encode_wide().chain(once(0)).collect();
+    let mut free = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(wide.as_ptr(), &mut free, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+    (ok != 0).then_some(free)
+}
+
+#[cfg(not(windows))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
@@ -0,0 +1,2 @@
+﻿#[allow(unused_imports)]
+pub use configarc_core::http::*;
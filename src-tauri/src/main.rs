@@ -3,43 +3,108 @@ mod config;
 mod error;
 mod fsdecrypt;
 mod games;
+mod http;
 mod sync;
 mod trusted;
 mod vhd;
 
+use crate::sync::ConfigManager;
 use crate::vhd::VhdMountHandle;
 use std::sync::{atomic::AtomicBool, Arc, Mutex};
 
 pub struct IrisState {
     pub mount: Arc<Mutex<Option<VhdMountHandle>>>,
+    /// Game whose VHD `mount` holds; only meaningful while `mount` is set.
+    pub mounted_game: Mutex<Option<String>>,
+    /// Id of the game started by `launch_game_internal` and PID of its launch process,
+    /// while it runs.
+    pub running_game: Arc<Mutex<Option<(String, u32)>>>,
     pub confirmed_launch: AtomicBool,
+    pub startup_cache: Mutex<Option<commands::StartupCache>>,
 }
 
 fn main() {
+    std::thread::spawn(commands::cleanup_stale_vhd_for_active);
+
     tauri::Builder::default()
         .manage(IrisState {
             mount: Arc::new(Mutex::new(None)),
+            mounted_game: Mutex::new(None),
+            running_game: Arc::new(Mutex::new(None)),
             confirmed_launch: AtomicBool::new(false),
+            startup_cache: Mutex::new(None),
+        })
+        .setup(|app| {
+            if let Ok(manager) = ConfigManager::new(app.handle()) {
+                manager.apply_overrides();
+            }
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_local_override_cmd,
             commands::set_local_override_cmd,
             commands::get_effective_config_cmd,
+            commands::get_effective_config_sources_cmd,
             commands::sync_remote_config_cmd,
+            commands::clear_remote_cache_cmd,
+            commands::check_updates_cmd,
             commands::apply_games_from_config_cmd,
             commands::list_games_cmd,
+            commands::list_games_by_tag_cmd,
+            commands::all_tags_cmd,
+            commands::duplicate_game_cmd,
+            commands::rename_game_id_cmd,
             commands::save_game_cmd,
+            commands::save_game_checked_cmd,
             commands::delete_game_cmd,
+            commands::reorder_games_cmd,
             commands::get_active_game_id_cmd,
             commands::set_active_game_id_cmd,
             commands::get_active_game_cmd,
             commands::load_segatools_config_cmd,
+            commands::reset_segatools_section_cmd,
+            commands::set_section_enabled_cmd,
+            commands::validate_serials_cmd,
+            commands::validate_clock_cmd,
+            commands::list_device_files_cmd,
+            commands::generate_aime_card_cmd,
+            commands::list_network_profiles_cmd,
+            commands::save_network_profile_cmd,
+            commands::apply_network_profile_cmd,
+            commands::export_bundle_cmd,
+            commands::import_bundle_cmd,
+            commands::verify_segatools_trust_cmd,
+            commands::deploy_segatools_cmd,
+            commands::list_backup_cmd,
+            commands::generate_keys_template_cmd,
+            commands::estimate_decrypt_space_cmd,
+            commands::open_segatools_dir_cmd,
+            commands::game_readiness_cmd,
+            commands::inspect_segatools_ini_cmd,
             commands::save_segatools_config_cmd,
+            commands::segatools_save_would_change_cmd,
             commands::default_segatools_config_cmd,
+            commands::validate_json_config_cmd,
+            commands::save_json_config_cmd,
+            commands::patch_json_config_cmd,
+            commands::restore_json_config_cmd,
+            commands::get_vhd_config_cmd,
+            commands::set_vhd_config_cmd,
+            commands::set_vhd_delta_cmd,
+            commands::validate_vhd_cmd,
+            commands::available_drive_letters_cmd,
+            commands::create_patch_vhd_cmd,
             commands::scan_game_folder_cmd,
+            commands::scan_game_folder_multi_cmd,
+            commands::import_games_from_parent_cmd,
             commands::confirm_launch_cmd,
             commands::run_startup_flow_cmd,
             commands::launch_active_game_cmd,
+            commands::stop_active_game_cmd,
+            commands::launch_game_debug_cmd,
+            commands::mount_vhd_cmd,
+            commands::vhd_mount_status_cmd,
+            commands::unmount_vhd_cmd,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
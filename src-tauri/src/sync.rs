@@ -1,16 +1,105 @@
 ﻿use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::thread::sleep;
 use std::time::Duration;
+use crate::config::json_configs::merge_json;
+use crate::error::ConfigError;
+use crate::http::{build_client, set_use_system_proxy, HttpOptions};
+use crate::trusted::{set_trusted_overrides, TrustedOverrides};
 use tauri::{AppHandle, Manager};
 
+const MAX_SYNC_RETRIES: u32 = 10;
+const RETRY_BASE_DELAY_MS: u64 = 500;
+const RETRY_MAX_DELAY_MS: u64 = 8000;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RemoteCache {
     pub fetched_at: Option<String>,
     pub config: Value,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+}
+
+enum FetchOutcome {
+    Modified {
+        config: Value,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+    NotModified,
+}
+
+/// Typed view of the remote config sections the launcher reads. Every section is
+/// optional, but when present it must have this shape; unknown keys are ignored.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RemoteConfigSchema {
+    pub machine: Option<RemoteMachineSection>,
+    pub updates: Option<RemoteUpdatesSection>,
+    pub startup: Option<RemoteStartupSection>,
+    pub games: Option<Vec<RemoteGameEntry>>,
+    pub vhd: Option<RemoteVhdSection>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RemoteMachineSection {
+    pub authorized: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RemoteUpdatesSection {
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RemoteStartupSection {
+    pub confirm_launch: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RemoteGameEntry {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RemoteVhdSection {
+    pub decrypt: Option<RemoteDecryptSection>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RemoteDecryptSection {
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(alias = "keyUrl")]
+    pub key_url: Option<String>,
+    /// Expected SHA-256 of the file at `key_url`.
+    #[serde(alias = "keySha256")]
+    pub key_sha256: Option<String>,
+    /// Where decrypted output goes; next to each container when unset.
+    #[serde(alias = "outputDir")]
+    pub output_dir: Option<String>,
+    /// Leave containers alone when their decrypted output already exists.
+    #[serde(default, alias = "skipExisting")]
+    pub skip_existing: bool,
+    /// Write `decrypt_manifest.json` with the size and SHA-256 of every output file.
+    #[serde(default, alias = "writeManifest")]
+    pub write_manifest: bool,
+}
+
+/// Effective config plus a parallel tree whose leaves are `"remote"`, `"local"` or `"both"`
+/// (present in both, local value wins). Arrays are treated as leaves.
+#[derive(Debug, Serialize, Clone)]
+pub struct EffectiveConfigWithSource {
+    pub config: Value,
+    pub sources: Value,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -21,6 +110,26 @@ pub struct SyncStatus {
     pub endpoint: Option<String>,
     pub used_cache: bool,
     pub error: Option<String>,
+    /// `ConfigError::code` of `error`: `network` means the cached config is still in use,
+    /// `parse` that the endpoint served a bad config.
+    #[serde(default)]
+    pub error_code: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl SyncStatus {
+    fn failed(endpoint: Option<String>, used_cache: bool, attempts: u32, err: ConfigError) -> SyncStatus {
+        SyncStatus {
+            ok: false,
+            fetched_at: None,
+            endpoint,
+            used_cache,
+            error: Some(err.to_string()),
+            error_code: Some(err.code().to_string()),
+            attempts,
+        }
+    }
 }
 
 pub struct ConfigManager {
@@ -50,7 +159,36 @@ impl ConfigManager {
     }
 
     pub fn write_local_override(&self, value: &Value) -> Result<(), String> {
-        write_json_value(&self.local_override_path, value)
+        write_json_value(&self.local_override_path, value)?;
+        self.apply_overrides();
+        Ok(())
+    }
+
+    /// Pushes the `trusted.*` and `network.*` settings from the local override to the
+    /// modules that use them.
+    pub fn apply_overrides(&self) {
+        let local = self.read_local_override();
+        set_use_system_proxy(
+            local
+                .pointer("/network/use_system_proxy")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false),
+        );
+        let read = |pointer: &str| {
+            local
+                .pointer(pointer)
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_string())
+        };
+        set_trusted_overrides(TrustedOverrides {
+            base_url: read("/trusted/base_url"),
+            prefix: read("/trusted/prefix"),
+            public_key: read("/trusted/public_key"),
+            backup_generations: local
+                .pointer("/trusted/backup_generations")
+                .and_then(|value| value.as_u64())
+                .map(|value| value as usize),
+        });
     }
 
     pub fn read_remote_cache(&self) -> RemoteCache {
@@ -62,6 +200,8 @@ impl ConfigManager {
         RemoteCache {
             fetched_at: None,
             config: Value::Null,
+            etag: None,
+            last_modified: None,
         }
     }
 
@@ -70,10 +210,42 @@ impl ConfigManager {
         write_json_value(&self.remote_cache_path, &value)
     }
 
+    /// Deletes the cached remote config; returns whether a cache file existed.
+    pub fn clear_remote_cache(&self) -> Result<bool, String> {
+        if !self.remote_cache_path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(&self.remote_cache_path).map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
     pub fn effective_config(&self) -> Value {
         let remote = self.read_remote_cache().config;
         let local = self.read_local_override();
-        merge_json(&remote, &local)
+        let mut merged = merge_json(&remote, &local);
+        // Local game overrides patch the remote entries instead of replacing the whole list.
+        if let (Some(Value::Array(remote_games)), Some(Value::Array(local_games)), Some(merged_map)) =
+            (remote.get("games"), local.get("games"), merged.as_object_mut())
+        {
+            merged_map.insert(
+                "games".to_string(),
+                Value::Array(merge_arrays_by_key(remote_games, local_games, "id")),
+            );
+        }
+        merged
+    }
+
+    pub fn effective_config_with_source(&self) -> EffectiveConfigWithSource {
+        let remote = self.read_remote_cache().config;
+        let local = self.read_local_override();
+        let sources = merge_json_sources(
+            Some(&remote).filter(|value| !value.is_null()),
+            Some(&local),
+        );
+        EffectiveConfigWithSource {
+            config: self.effective_config(),
+            sources,
+        }
     }
 
     pub fn resolve_endpoint(&self, override_endpoint: Option<String>) -> Option<String> {
@@ -107,74 +279,100 @@ impl ConfigManager {
         headers
     }
 
+    fn resolve_retries(&self) -> u32 {
+        self.read_local_override()
+            .pointer("/remote/retries")
+            .and_then(|value| value.as_u64())
+            .map(|value| value.min(MAX_SYNC_RETRIES as u64) as u32)
+            .unwrap_or(0)
+    }
+
     pub fn sync_remote(&self, endpoint_override: Option<String>) -> SyncStatus {
         let endpoint = self.resolve_endpoint(endpoint_override);
         let used_cache = self.remote_cache_path.exists();
         let Some(endpoint) = endpoint else {
-            return SyncStatus {
-                ok: false,
-                fetched_at: None,
-                endpoint: None,
+            return SyncStatus::failed(
+                None,
                 used_cache,
-                error: Some("Missing remote endpoint".to_string()),
-            };
+                0,
+                ConfigError::NotFound("Missing remote endpoint".to_string()),
+            );
         };
 
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(6))
-            .connect_timeout(Duration::from_secs(4))
-            .build()
-        {
+        let client = match build_client(HttpOptions::quick()) {
             Ok(client) => client,
             Err(err) => {
-                return SyncStatus {
-                    ok: false,
-                    fetched_at: None,
-                    endpoint: Some(endpoint),
-                    used_cache,
-                    error: Some(err.to_string()),
-                };
+                return SyncStatus::failed(Some(endpoint), used_cache, 0, ConfigError::Network(err.to_string()));
             }
         };
 
-        let mut request = client.get(&endpoint);
-        for (key, value) in self.resolve_headers() {
-            request = request.header(&key, &value);
+        let mut headers = self.resolve_headers();
+        let cached = self.read_remote_cache();
+        if !cached.config.is_null() {
+            if let Some(etag) = &cached.etag {
+                headers.insert(IF_NONE_MATCH.to_string(), etag.clone());
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                headers.insert(IF_MODIFIED_SINCE.to_string(), last_modified.clone());
+            }
         }
-
-        match request.send().and_then(|response| response.error_for_status()) {
-            Ok(response) => match response.json::<Value>() {
-                Ok(config) => {
+        let max_attempts = self.resolve_retries() + 1;
+        let mut attempts = 0;
+        let mut last_error = None;
+        while attempts < max_attempts {
+            if attempts > 0 {
+                sleep(retry_delay(attempts));
+            }
+            attempts += 1;
+            match fetch_remote(&client, &endpoint, &headers) {
+                Ok(FetchOutcome::NotModified) => {
+                    return SyncStatus {
+                        ok: true,
+                        fetched_at: cached.fetched_at,
+                        endpoint: Some(endpoint),
+                        used_cache: true,
+                        error: None,
+                        error_code: None,
+                        attempts,
+                    };
+                }
+                Ok(FetchOutcome::Modified {
+                    config,
+                    etag,
+                    last_modified,
+                }) => {
+                    // A malformed config is not retried; the previous cache stays in place.
+                    if let Err(err) = validate_remote_config(&config) {
+                        return SyncStatus::failed(Some(endpoint), used_cache, attempts, ConfigError::Parse(err));
+                    }
                     let fetched_at = chrono::Utc::now().to_rfc3339();
                     let cache = RemoteCache {
                         fetched_at: Some(fetched_at.clone()),
                         config,
+                        etag,
+                        last_modified,
                     };
                     let _ = self.write_remote_cache(&cache);
-                    SyncStatus {
+                    return SyncStatus {
                         ok: true,
                         fetched_at: Some(fetched_at),
                         endpoint: Some(endpoint),
                         used_cache,
                         error: None,
-                    }
+                        error_code: None,
+                        attempts,
+                    };
                 }
-                Err(err) => SyncStatus {
-                    ok: false,
-                    fetched_at: None,
-                    endpoint: Some(endpoint),
-                    used_cache,
-                    error: Some(err.to_string()),
-                },
-            },
-            Err(err) => SyncStatus {
-                ok: false,
-                fetched_at: None,
-                endpoint: Some(endpoint),
-                used_cache,
-                error: Some(err.to_string()),
-            },
+                // A body that is not JSON will not get better on retry either.
+                Err(err @ ConfigError::Parse(_)) => {
+                    return SyncStatus::failed(Some(endpoint), used_cache, attempts, err);
+                }
+                Err(err) => last_error = Some(err),
+            }
         }
+
+        let err = last_error.unwrap_or_else(|| ConfigError::Network("No attempts made".to_string()));
+        SyncStatus::failed(Some(endpoint), used_cache, attempts, err)
     }
 
     pub fn root_dir(&self) -> &Path {
@@ -182,6 +380,95 @@ impl ConfigManager {
     }
 }
 
+fn retry_delay(attempt: u32) -> Duration {
+    let factor = 1u64 << (attempt - 1).min(16);
+    Duration::from_millis((RETRY_BASE_DELAY_MS * factor).min(RETRY_MAX_DELAY_MS))
+}
+
+fn fetch_remote(
+    client: &Client,
+    endpoint: &str,
+    headers: &HashMap<String, String>,
+) -> Result<FetchOutcome, ConfigError> {
+    let mut request = client.get(endpoint);
+    for (key, value) in headers {
+        request = request.header(key, value);
+    }
+    let response = request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| ConfigError::Network(e.to_string()))?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(FetchOutcome::NotModified);
+    }
+    let header_value = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let etag = header_value(ETAG);
+    let last_modified = header_value(LAST_MODIFIED);
+    let config = response.json::<Value>().map_err(|e| {
+        if e.is_decode() {
+            ConfigError::Parse(format!("Remote config is not valid JSON: {e}"))
+        } else {
+            ConfigError::Network(e.to_string())
+        }
+    })?;
+    Ok(FetchOutcome::Modified {
+        config,
+        etag,
+        last_modified,
+    })
+}
+
+pub fn validate_remote_config(config: &Value) -> Result<RemoteConfigSchema, String> {
+    let Some(obj) = config.as_object() else {
+        return Err("Invalid remote config: expected a JSON object".to_string());
+    };
+    let schema = RemoteConfigSchema {
+        machine: remote_section(obj, "machine")?,
+        updates: remote_section(obj, "updates")?,
+        startup: remote_section(obj, "startup")?,
+        games: remote_section(obj, "games")?,
+        vhd: remote_section(obj, "vhd")?,
+    };
+    if let Some(games) = &schema.games {
+        if games.iter().any(|game| game.id.trim().is_empty()) {
+            return Err("Invalid remote config `games`: every entry needs a non-empty id".to_string());
+        }
+    }
+    Ok(schema)
+}
+
+/// Reads the sections of `config` the way [`validate_remote_config`] does, but a section
+/// that fails to parse is left out on its own instead of failing the whole schema.
+pub fn remote_config_sections(config: &Value) -> RemoteConfigSchema {
+    let Some(obj) = config.as_object() else {
+        return RemoteConfigSchema::default();
+    };
+    RemoteConfigSchema {
+        machine: remote_section(obj, "machine").unwrap_or_default(),
+        updates: remote_section(obj, "updates").unwrap_or_default(),
+        startup: remote_section(obj, "startup").unwrap_or_default(),
+        games: remote_section::<Vec<RemoteGameEntry>>(obj, "games")
+            .unwrap_or_default()
+            .filter(|games| games.iter().all(|game| !game.id.trim().is_empty())),
+        vhd: remote_section(obj, "vhd").unwrap_or_default(),
+    }
+}
+
+fn remote_section<T: DeserializeOwned>(obj: &Map<String, Value>, key: &str) -> Result<Option<T>, String> {
+    match obj.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| format!("Invalid remote config `{key}`: {e}")),
+    }
+}
+
 fn read_json_value(path: &Path) -> Option<Value> {
     let data = fs::read_to_string(path).ok()?;
     if data.trim().is_empty() {
@@ -201,21 +488,54 @@ fn write_json_value(path: &Path, value: &Value) -> Result<(), String> {
     Ok(())
 }
 
-fn merge_json(base: &Value, overlay: &Value) -> Value {
+/// Mirrors `merge_json`, recording where each leaf of the merged value came from.
+fn merge_json_sources(base: Option<&Value>, overlay: Option<&Value>) -> Value {
     match (base, overlay) {
-        (Value::Object(base_map), Value::Object(overlay_map)) => {
-            let mut merged = base_map.clone();
+        (Some(Value::Object(base_map)), Some(Value::Object(overlay_map))) => {
+            let mut sources = Map::new();
+            for (key, value) in base_map {
+                sources.insert(key.clone(), merge_json_sources(Some(value), overlay_map.get(key)));
+            }
             for (key, value) in overlay_map {
-                let next = if let Some(existing) = merged.get(key) {
-                    merge_json(existing, value)
-                } else {
-                    value.clone()
-                };
-                merged.insert(key.clone(), next);
+                if !base_map.contains_key(key) {
+                    sources.insert(key.clone(), merge_json_sources(None, Some(value)));
+                }
             }
-            Value::Object(merged)
+            Value::Object(sources)
+        }
+        (Some(base_value), None) => mark_json_source(base_value, "remote"),
+        (None, Some(overlay_value)) => mark_json_source(overlay_value, "local"),
+        (Some(_), Some(overlay_value)) => mark_json_source(overlay_value, "both"),
+        (None, None) => Value::Null,
+    }
+}
+
+fn mark_json_source(value: &Value, source: &str) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), mark_json_source(value, source)))
+                .collect(),
+        ),
+        _ => Value::String(source.to_string()),
+    }
+}
+
+/// Merges two arrays of objects by `key`: matching entries are deep-merged, overlay-only
+/// entries are appended and base-only entries are kept in their original order.
+fn merge_arrays_by_key(base: &[Value], overlay: &[Value], key: &str) -> Vec<Value> {
+    let mut merged = base.to_vec();
+    for item in overlay {
+        let existing = item.get(key).and_then(|id| {
+            merged
+                .iter()
+                .position(|candidate| candidate.get(key) == Some(id))
+        });
+        match existing {
+            Some(index) => merged[index] = merge_json(&merged[index], item),
+            None => merged.push(item.clone()),
         }
-        (_, overlay_value) => overlay_value.clone(),
     }
+    merged
 }
 
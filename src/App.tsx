@@ -107,6 +107,7 @@ export default function App() {
   const [statusText, setStatusText] = useState(t("status.ready"));
   const [bootError, setBootError] = useState<string | null>(null);
   const [booting, setBooting] = useState(true);
+  const [awaitingConfirm, setAwaitingConfirm] = useState(false);
   const intervalRef = useRef<number | null>(null);
   const indexRef = useRef(0);
   const pendingResultRef = useRef<StartupResult | null>(null);
@@ -166,10 +167,27 @@ export default function App() {
     );
     setSteps(finalSteps);
     const hasError = finalSteps.some((step) => step.status === "error");
-    setStatusText(hasError ? t("status.failed") : t("status.done"));
+    const needsConfirm = finalSteps.some(
+      (step) => step.key === "steps.confirm" && step.status === "pending"
+    );
+    setAwaitingConfirm(needsConfirm);
+    setStatusText(
+      hasError ? t("status.failed") : needsConfirm ? t("status.awaitingConfirm") : t("status.done")
+    );
     setBooting(false);
   };
 
+  const confirmLaunch = async () => {
+    setAwaitingConfirm(false);
+    try {
+      await invokeCmd("confirm_launch_cmd");
+    } catch (err) {
+      setBootError(err instanceof Error ? err.message : t("status.failed"));
+      return;
+    }
+    startBoot();
+  };
+
   const applyStubProgress = () => {
     if (indexRef.current >= BOOT_STEPS.length) {
       setSteps((prev) =>
@@ -211,6 +229,7 @@ export default function App() {
   const startBoot = () => {
     setBooting(true);
     setBootError(null);
+    setAwaitingConfirm(false);
     setSteps(BOOT_STEPS);
     setStatusText(t("status.ready"));
     indexRef.current = 0;
@@ -319,8 +338,15 @@ export default function App() {
               )}
             </div>
 
+            {awaitingConfirm && (
+              <button className="boot-confirm" onClick={() => void confirmLaunch()}>
+                {t("actions.confirmLaunch")}
+              </button>
+            )}
             {bootError && <div className="boot-error">{bootError}</div>}
-            {!booting && !bootError && <div className="boot-done">{t("status.idle")}</div>}
+            {!booting && !bootError && !awaitingConfirm && (
+              <div className="boot-done">{t("status.idle")}</div>
+            )}
           </div>
         </div>
       </div>
@@ -359,8 +385,15 @@ export default function App() {
             )}
           </div>
 
+          {awaitingConfirm && (
+            <button className="boot-confirm" onClick={() => void confirmLaunch()}>
+              {t("actions.confirmLaunch")}
+            </button>
+          )}
           {bootError && <div className="boot-error">{bootError}</div>}
-          {!booting && !bootError && <div className="boot-done">{t("status.idle")}</div>}
+          {!booting && !bootError && !awaitingConfirm && (
+            <div className="boot-done">{t("status.idle")}</div>
+          )}
         </div>
       </div>
     </div>
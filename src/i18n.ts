@@ -35,7 +35,11 @@ i18n
             finishing: "即将完成",
             failed: "启动失败",
             done: "启动完成",
-            idle: "待机中"
+            idle: "待机中",
+            awaitingConfirm: "等待确认启动配置"
+          },
+          actions: {
+            confirmLaunch: "确认启动"
           },
           errors: {
             machineUnauthorized: "机台未授权",
@@ -74,7 +78,11 @@ i18n
             finishing: "Finishing",
             failed: "Boot failed",
             done: "Boot complete",
-            idle: "Idle"
+            idle: "Idle",
+            awaitingConfirm: "Waiting for launch confirmation"
+          },
+          actions: {
+            confirmLaunch: "Confirm launch"
           },
           errors: {
             machineUnauthorized: "Machine unauthorized",
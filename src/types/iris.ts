@@ -1,4 +1,5 @@
 ﻿export type StartupStep = {
+  key: string;
   name: string;
   status: "pending" | "running" | "ok" | "skipped" | "warning" | "error";
   detail?: string | null;